@@ -0,0 +1,19 @@
+#![no_main]
+
+use http::{Request, Response};
+use libfuzzer_sys::fuzz_target;
+use pext::{FromUtf8Framed, PartialRequest, PartialResponse, RequestHead, ResponseHead};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Request::<Vec<u8>>::from_utf8_framed(data);
+    let _ = Response::<Vec<u8>>::from_utf8_framed(data);
+    let _ = RequestHead::parse(data).and_then(|(head, _)| head.to_request(()));
+    let _ = ResponseHead::parse(data).and_then(|(head, _)| head.to_response(()));
+
+    let mut request = PartialRequest::new();
+    let mut response = PartialResponse::new();
+    for chunk in data.chunks(3) {
+        let _ = request.feed(chunk);
+        let _ = response.feed(chunk);
+    }
+});
@@ -1,5 +1,5 @@
 use nom::{
-    branch::*, bytes::complete::*, character::complete::*, combinator::*, multi::*, sequence::*,
+    branch::*, bytes::streaming::*, character::streaming::*, combinator::*, multi::*, sequence::*,
     IResult,
 };
 
@@ -25,7 +25,9 @@ pub fn token(input: &[u8]) -> IResult<&[u8], &[u8]> {
 }
 
 pub fn tchar(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    map_parser(take(1usize), alt((is_a("!#$%&'*+-.^_`|~"), digit1, alpha1)))(input)
+    take_while_m_n(1, 1, |c: u8| {
+        c.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&c)
+    })(input)
 }
 
 pub fn htab(input: &[u8]) -> IResult<&[u8], &[u8]> {
@@ -33,15 +35,9 @@ pub fn htab(input: &[u8]) -> IResult<&[u8], &[u8]> {
 }
 
 pub fn vchar(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let (rest, _) = satisfy(|c| c >= 0x21 as char && c <= 0x7e as char)(input)?;
-    let len = input.len() - rest.len();
-
-    Ok((rest, &input[0..len]))
+    take_while_m_n(1, 1, |c: u8| (0x21..=0x7e).contains(&c))(input)
 }
 
 pub fn obs_text(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    let (rest, _) = satisfy(|c| c >= 0x80 as char && c <= 0xFF as char)(input)?;
-    let len = input.len() - rest.len();
-
-    Ok((rest, &input[0..len]))
+    take_while_m_n(1, 1, |c: u8| c >= 0x80)(input)
 }
@@ -0,0 +1,368 @@
+use http::header::{CONTENT_LENGTH, HOST, TRAILER, TRANSFER_ENCODING};
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+
+use nom::{Needed, Offset};
+
+use crate::chunked::{chunked_message, BodyWithTrailers};
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::fields::FieldLine;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyLength {
+    NoBody,
+    Fixed(usize),
+    Chunked,
+    UntilClose,
+}
+
+impl BodyLength {
+    pub fn read_body<'a>(
+        &self,
+        input: &'a [u8],
+        config: &ParserConfig,
+    ) -> Result<(Vec<u8>, &'a [u8]), FromUtf8Err> {
+        let (body, _trailers, rest) = self.read_body_with_trailers(input, config)?;
+
+        Ok((body, rest))
+    }
+
+    pub fn read_body_with_trailers<'a>(
+        &self,
+        input: &'a [u8],
+        config: &ParserConfig,
+    ) -> Result<BodyWithTrailers<'a>, FromUtf8Err> {
+        match *self {
+            BodyLength::NoBody => Ok((Vec::new(), HeaderMap::new(), input)),
+            BodyLength::Fixed(len) if input.len() < len => Err(FromUtf8Err::init(
+                Vec::new(),
+                ErrorKind::Incomplete(Needed::new(len - input.len())),
+            )
+            .at(input.len())),
+            BodyLength::Fixed(len) => Ok((input[..len].to_vec(), HeaderMap::new(), &input[len..])),
+            BodyLength::Chunked => chunked_message(input, config),
+            BodyLength::UntilClose => Ok((input.to_vec(), HeaderMap::new(), &input[input.len()..])),
+        }
+    }
+}
+
+pub fn request_body_length(headers: &HeaderMap) -> Result<BodyLength, FromUtf8Err> {
+    if headers.contains_key(TRANSFER_ENCODING) {
+        return if is_chunked(headers) {
+            Ok(BodyLength::Chunked)
+        } else {
+            let value = headers.get_all(TRANSFER_ENCODING).iter().next_back();
+            let value = value.map(HeaderValue::as_bytes).unwrap_or_default();
+            Err(FromUtf8Err::init(value, ErrorKind::TransferEncoding))
+        };
+    }
+
+    Ok(BodyLength::Fixed(content_length(headers)?.unwrap_or(0)))
+}
+
+pub fn response_body_length_for(
+    method: &Method,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Result<BodyLength, FromUtf8Err> {
+    if method == Method::HEAD || (method == Method::CONNECT && status.is_success()) {
+        return Ok(BodyLength::NoBody);
+    }
+
+    response_body_length(status, headers)
+}
+
+pub fn response_body_length(
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Result<BodyLength, FromUtf8Err> {
+    if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+    {
+        return Ok(BodyLength::NoBody);
+    }
+
+    if headers.contains_key(TRANSFER_ENCODING) {
+        return Ok(if is_chunked(headers) {
+            BodyLength::Chunked
+        } else {
+            BodyLength::UntilClose
+        });
+    }
+
+    Ok(match content_length(headers)? {
+        Some(len) => BodyLength::Fixed(len),
+        None => BodyLength::UntilClose,
+    })
+}
+
+pub fn is_chunked(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(TRANSFER_ENCODING)
+        .iter()
+        .flat_map(|value| value.as_bytes().split(|&c| c == b','))
+        .map(|coding| coding.trim_ascii())
+        .rfind(|coding| !coding.is_empty())
+        .is_some_and(|coding| coding.eq_ignore_ascii_case(b"chunked"))
+}
+
+pub fn validate_trailers(headers: &HeaderMap, trailers: &HeaderMap) -> Result<(), FromUtf8Err> {
+    let declared: Vec<_> = headers
+        .get_all(TRAILER)
+        .iter()
+        .flat_map(|value| value.as_bytes().split(|&c| c == b','))
+        .map(|name| name.trim_ascii())
+        .collect();
+
+    for name in trailers.keys() {
+        let framing = [CONTENT_LENGTH, TRANSFER_ENCODING, TRAILER, HOST].contains(name);
+        let is_declared = declared
+            .iter()
+            .any(|declared| declared.eq_ignore_ascii_case(name.as_str().as_bytes()));
+
+        if framing || !is_declared {
+            return Err(FromUtf8Err::init(name.as_str(), ErrorKind::Trailer));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn content_length(headers: &HeaderMap) -> Result<Option<usize>, FromUtf8Err> {
+    let mut result = None;
+
+    for value in headers.get_all(CONTENT_LENGTH) {
+        for item in value.as_bytes().split(|&c| c == b',') {
+            let len = std::str::from_utf8(item.trim_ascii())
+                .ok()
+                .filter(|v| !v.is_empty() && v.bytes().all(|c| c.is_ascii_digit()))
+                .and_then(|v| v.parse::<usize>().ok())
+                .ok_or_else(|| FromUtf8Err::init(value.as_bytes(), ErrorKind::ContentLength))?;
+
+            match result {
+                Some(prev) if prev != len => {
+                    return Err(FromUtf8Err::init(
+                        value.as_bytes(),
+                        ErrorKind::ContentLength,
+                    ))
+                }
+                _ => result = Some(len),
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FramingFields(Vec<(Position, Vec<u8>)>);
+
+impl FramingFields {
+    pub(crate) fn collect(input: &[u8], fields: &[FieldLine<'_>], start: &Position) -> Self {
+        let mut position = start.clone();
+        let mut advanced = 0;
+        let mut lines = Vec::new();
+
+        for &(name, _) in fields {
+            let framing = [CONTENT_LENGTH, TRANSFER_ENCODING]
+                .iter()
+                .any(|framing| name.eq_ignore_ascii_case(framing.as_str().as_bytes()));
+            if !framing {
+                continue;
+            }
+
+            let at = input.offset(name);
+            position.advance(&input[advanced..at]);
+            advanced = at;
+
+            let line = &input[at..];
+            let len = line
+                .iter()
+                .position(|&c| c == b'\r' || c == b'\n')
+                .unwrap_or(line.len());
+            lines.push((position.clone(), line[..len].to_vec()));
+        }
+
+        Self(lines)
+    }
+
+    pub(crate) fn locate(&self, e: FromUtf8Err) -> FromUtf8Err {
+        let name = match e.kind() {
+            ErrorKind::ContentLength => CONTENT_LENGTH,
+            ErrorKind::TransferEncoding => TRANSFER_ENCODING,
+            _ => return e,
+        };
+
+        let found = self.0.iter().find_map(|(position, line)| {
+            let colon = line.iter().position(|&c| c == b':')?;
+            let value =
+                colon + 1 + line[colon + 1..].len() - line[colon + 1..].trim_ascii_start().len();
+            let at = value + find(&line[value..], e.input())?;
+
+            line[..colon]
+                .trim_ascii_end()
+                .eq_ignore_ascii_case(name.as_str().as_bytes())
+                .then_some((position, line, at))
+        });
+
+        match found {
+            Some((position, line, at)) => e.at(at).locate(line, position),
+            None => e,
+        }
+    }
+}
+
+pub(crate) fn locate_trailer(e: FromUtf8Err, body: &[u8], start: &Position) -> FromUtf8Err {
+    let name = e.input();
+    let at = (0..body.len()).rev().find(|&i| {
+        let line = &body[i..];
+        (i == 0 || body[i - 1] == b'\n')
+            && line.len() > name.len()
+            && line[..name.len()].eq_ignore_ascii_case(name)
+            && matches!(line[name.len()], b':' | b' ' | b'\t')
+    });
+
+    match at {
+        Some(at) => e.at(at).locate(body, start),
+        None => e,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_content_length() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(content_length(&headers).unwrap(), None);
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("10"));
+        assert_eq!(content_length(&headers).unwrap(), Some(10));
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("10 , 10"));
+        assert_eq!(content_length(&headers).unwrap(), Some(10));
+
+        headers.append(CONTENT_LENGTH, HeaderValue::from_static("10, 11"));
+        let err = content_length(&headers).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ContentLength);
+        assert_eq!(err.input(), b"10, 11");
+
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("+10"));
+        assert_eq!(
+            content_length(&headers).unwrap_err().kind(),
+            &ErrorKind::ContentLength
+        );
+    }
+
+    #[test]
+    fn test_body_length() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("10"));
+        assert_eq!(
+            request_body_length(&headers).unwrap(),
+            BodyLength::Fixed(10)
+        );
+
+        headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("gzip, Chunked"));
+        assert_eq!(request_body_length(&headers).unwrap(), BodyLength::Chunked);
+
+        headers.insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked, gzip"));
+        assert!(request_body_length(&headers).is_err());
+        assert_eq!(
+            response_body_length(StatusCode::OK, &headers).unwrap(),
+            BodyLength::UntilClose
+        );
+    }
+
+    #[test]
+    fn test_validate_trailers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(TRAILER, HeaderValue::from_static("Expires, Server-Timing"));
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert("server-timing", HeaderValue::from_static("db;dur=53"));
+        assert!(validate_trailers(&headers, &trailers).is_ok());
+
+        trailers.insert("x-undeclared", HeaderValue::from_static("1"));
+        let err = validate_trailers(&headers, &trailers).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Trailer);
+        assert_eq!(err.input(), b"x-undeclared");
+
+        headers.append(
+            TRAILER,
+            HeaderValue::from_static("x-undeclared, content-length"),
+        );
+        assert!(validate_trailers(&headers, &trailers).is_ok());
+
+        trailers.insert(CONTENT_LENGTH, HeaderValue::from_static("4"));
+        assert!(validate_trailers(&headers, &trailers).is_err());
+    }
+
+    #[test]
+    fn test_response_body_length_for() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("10"));
+
+        let cases = [
+            (Method::GET, StatusCode::OK, BodyLength::Fixed(10)),
+            (Method::HEAD, StatusCode::OK, BodyLength::NoBody),
+            (Method::GET, StatusCode::CONTINUE, BodyLength::NoBody),
+            (
+                Method::GET,
+                StatusCode::SWITCHING_PROTOCOLS,
+                BodyLength::NoBody,
+            ),
+            (Method::POST, StatusCode::NO_CONTENT, BodyLength::NoBody),
+            (Method::GET, StatusCode::NOT_MODIFIED, BodyLength::NoBody),
+            (Method::CONNECT, StatusCode::OK, BodyLength::NoBody),
+            (
+                Method::CONNECT,
+                StatusCode::FORBIDDEN,
+                BodyLength::Fixed(10),
+            ),
+        ];
+        for (method, status, expected) in cases {
+            assert_eq!(
+                response_body_length_for(&method, status, &headers).unwrap(),
+                expected,
+                "{} {}",
+                method,
+                status
+            );
+        }
+
+        headers.clear();
+        assert_eq!(
+            response_body_length_for(&Method::GET, StatusCode::OK, &headers).unwrap(),
+            BodyLength::UntilClose
+        );
+    }
+
+    #[test]
+    fn test_read_body() {
+        let (body, rest) = BodyLength::Fixed(4)
+            .read_body(b"bodyGET", &ParserConfig::default())
+            .unwrap();
+        assert_eq!(body, b"body");
+        assert_eq!(rest, b"GET");
+
+        let err = BodyLength::Fixed(10)
+            .read_body(b"body", &ParserConfig::default())
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Incomplete(Needed::new(6)));
+    }
+}
@@ -0,0 +1,139 @@
+use http::HeaderMap;
+
+use nom::{bytes::streaming::*, character::streaming::crlf, sequence::*, Offset};
+
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::fields::{header_map, parse_field_lines, write_fields};
+use crate::http_combinator::*;
+use crate::limits::check_fields;
+
+pub type BodyWithTrailers<'a> = (Vec<u8>, HeaderMap, &'a [u8]);
+
+pub fn decode_chunked(input: &[u8]) -> Result<(Vec<u8>, &[u8]), FromUtf8Err> {
+    let (body, _trailers, rest) = decode_chunked_with_trailers(input)?;
+
+    Ok((body, rest))
+}
+
+pub fn decode_chunked_with_trailers(input: &[u8]) -> Result<BodyWithTrailers<'_>, FromUtf8Err> {
+    chunked_message(input, &ParserConfig::default())
+        .map_err(|e| e.locate(input, &Position::default()))
+}
+
+pub(crate) fn chunked_message<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Result<BodyWithTrailers<'a>, FromUtf8Err> {
+    let mut body = Vec::new();
+    let mut rest = input;
+
+    loop {
+        let (after_size, (size, _ext)) = terminated(tuple((chunk_size, chunk_ext)), crlf)(rest)
+            .map_err(|e| e.into_parse_error(input, ErrorKind::Chunk))?;
+
+        let size = std::str::from_utf8(size)
+            .ok()
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or_else(|| FromUtf8Err::init(size, ErrorKind::Chunk).at(input.offset(size)))?;
+
+        if size == 0 {
+            let shift = |e: FromUtf8Err| e.shift(input.offset(after_size));
+            check_fields(after_size, 0, 0, config).map_err(shift)?;
+
+            let (after_trailers, fields, _) =
+                parse_field_lines(after_size, config).map_err(shift)?;
+            let trailers = header_map(input, &fields, config)?;
+
+            return Ok((body, trailers, after_trailers));
+        }
+
+        let (after_data, data) = terminated(take(size), crlf)(after_size)
+            .map_err(|e| e.into_parse_error(input, ErrorKind::Chunk))?;
+
+        body.extend_from_slice(data);
+        rest = after_data;
+    }
+}
+
+pub fn encode_chunked(body: &[u8]) -> Vec<u8> {
+    encode_chunked_with_trailers(body, &HeaderMap::new())
+}
+
+pub fn encode_chunked_with_trailers(body: &[u8], trailers: &HeaderMap) -> Vec<u8> {
+    let mut result = Vec::with_capacity(body.len() + 16);
+
+    if !body.is_empty() {
+        result.extend_from_slice(&chunk_header(body.len()));
+        result.extend_from_slice(body);
+        result.extend_from_slice(b"\r\n");
+    }
+    result.append(&mut last_chunk(trailers));
+
+    result
+}
+
+pub(crate) fn chunk_header(size: usize) -> Vec<u8> {
+    format!("{:X}\r\n", size).into_bytes()
+}
+
+pub(crate) fn last_chunk(trailers: &HeaderMap) -> Vec<u8> {
+    let mut result = b"0\r\n".to_vec();
+    write_fields(&mut result, trailers);
+    result.extend_from_slice(b"\r\n");
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_chunked() {
+        let input = b"4\r\nWiki\r\n6;name=\"value\"\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nNEXT";
+
+        let (body, rest) = decode_chunked(input).unwrap();
+        assert_eq!(body, b"Wikipedia in \r\n\r\nchunks.");
+        assert_eq!(rest, b"NEXT");
+
+        let err = decode_chunked(&input[..20]).unwrap_err();
+        assert!(err.is_incomplete());
+
+        let err = decode_chunked(b"4\r\nWikiX\r\n0\r\n\r\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Chunk);
+        assert_eq!((err.offset(), err.line(), err.column()), (7, 2, 5));
+    }
+
+    #[test]
+    fn test_trailers() {
+        let input = b"4\r\nWiki\r\n0\r\nexpires: never\r\nserver-timing: db;dur=53\r\n\r\nNEXT";
+
+        let (body, trailers, rest) = decode_chunked_with_trailers(input).unwrap();
+        assert_eq!(body, b"Wiki");
+        assert_eq!(trailers.get("expires").unwrap(), "never");
+        assert_eq!(trailers.get("server-timing").unwrap(), "db;dur=53");
+        assert_eq!(rest, b"NEXT");
+
+        assert_eq!(
+            encode_chunked_with_trailers(&body, &trailers),
+            &input[..input.len() - 4]
+        );
+
+        let err = decode_chunked_with_trailers(b"0\r\nBad Trailer\r\n\r\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Header);
+        assert_eq!((err.line(), err.column()), (2, 4));
+    }
+
+    #[test]
+    fn test_encode_chunked() {
+        let encoded = encode_chunked(b"Wikipedia in chunks.");
+        assert_eq!(encoded, b"14\r\nWikipedia in chunks.\r\n0\r\n\r\n");
+
+        let (body, rest) = decode_chunked(&encoded).unwrap();
+        assert_eq!(body, b"Wikipedia in chunks.");
+        assert!(rest.is_empty());
+
+        assert_eq!(encode_chunked(b""), b"0\r\n\r\n");
+    }
+}
@@ -0,0 +1,522 @@
+use bytes::{Buf, BytesMut};
+use http::{Method, Request, Response, StatusCode};
+use nom::{character::streaming::crlf, sequence::*};
+use tokio_util::codec::{Decoder, Encoder};
+
+use std::collections::VecDeque;
+
+use crate::body::BodyLength;
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::http_combinator::{chunk_ext, chunk_size};
+use crate::{PartialRequest, PartialResponse, WriteUtf8};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error(transparent)]
+    Parse(#[from] FromUtf8Err),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("message cannot be serialized")]
+    Serialize,
+}
+
+fn encode<T: WriteUtf8>(item: &T, dst: &mut BytesMut) -> Result<(), CodecError> {
+    let slices = item.to_io_slices().map_err(|_| CodecError::Serialize)?;
+
+    dst.reserve(slices.len());
+    for slice in slices.as_io_slices() {
+        dst.extend_from_slice(&slice);
+    }
+
+    Ok(())
+}
+
+fn complete<T>(
+    src: &mut BytesMut,
+    parsed: Result<(T, usize), FromUtf8Err>,
+) -> Result<Option<T>, CodecError> {
+    match parsed {
+        Ok((message, consumed)) => {
+            src.advance(consumed);
+            Ok(Some(message))
+        }
+        Err(e) if e.is_incomplete() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+trait Partial: Sized {
+    type Message;
+
+    fn with_config(config: ParserConfig) -> Self;
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err>;
+    fn rest(&self) -> &[u8];
+    fn body_length(&self, method: &Method) -> Result<BodyLength, FromUtf8Err>;
+    fn framed<'a>(
+        self,
+        input: &'a [u8],
+        method: &Method,
+    ) -> Result<(Self::Message, &'a [u8]), FromUtf8Err>;
+}
+
+impl Partial for PartialRequest {
+    type Message = Request<Vec<u8>>;
+
+    fn with_config(config: ParserConfig) -> Self {
+        PartialRequest::with_config(config)
+    }
+
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        PartialRequest::feed(self, input)
+    }
+
+    fn rest(&self) -> &[u8] {
+        PartialRequest::rest(self)
+    }
+
+    fn body_length(&self, _method: &Method) -> Result<BodyLength, FromUtf8Err> {
+        PartialRequest::body_length(self)
+    }
+
+    fn framed<'a>(
+        self,
+        input: &'a [u8],
+        _method: &Method,
+    ) -> Result<(Self::Message, &'a [u8]), FromUtf8Err> {
+        PartialRequest::framed(self, input)
+    }
+}
+
+impl Partial for PartialResponse {
+    type Message = Response<Vec<u8>>;
+
+    fn with_config(config: ParserConfig) -> Self {
+        PartialResponse::with_config(config)
+    }
+
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        PartialResponse::feed(self, input)
+    }
+
+    fn rest(&self) -> &[u8] {
+        PartialResponse::rest(self)
+    }
+
+    fn body_length(&self, method: &Method) -> Result<BodyLength, FromUtf8Err> {
+        PartialResponse::body_length_for(self, method)
+    }
+
+    fn framed<'a>(
+        self,
+        input: &'a [u8],
+        method: &Method,
+    ) -> Result<(Self::Message, &'a [u8]), FromUtf8Err> {
+        PartialResponse::framed_for(self, input, method)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chunk {
+    Size,
+    Data(usize),
+    DataEnd,
+    Trailers(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyState {
+    Fixed(usize),
+    Chunked(Chunk),
+    UntilClose,
+}
+
+fn line_len(input: &[u8]) -> Option<usize> {
+    input.iter().position(|&c| c == b'\n').map(|at| at + 1)
+}
+
+fn chunk_size_line(line: &[u8]) -> Option<usize> {
+    let (_, (size, _ext)) = terminated(tuple((chunk_size, chunk_ext)), crlf)(line).ok()?;
+
+    usize::from_str_radix(std::str::from_utf8(size).ok()?, 16).ok()
+}
+
+#[derive(Debug, Clone, Default)]
+struct Decoding<P> {
+    partial: P,
+    fed: usize,
+    head_len: usize,
+    scanned: usize,
+    body: Option<BodyState>,
+}
+
+impl<P: Partial> Decoding<P> {
+    fn new(config: &ParserConfig) -> Self {
+        Self {
+            partial: P::with_config(config.clone()),
+            fed: 0,
+            head_len: 0,
+            scanned: 0,
+            body: None,
+        }
+    }
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut,
+        method: &Method,
+        eof: bool,
+        config: &ParserConfig,
+    ) -> Result<Option<P::Message>, CodecError> {
+        match self.advance(src, method, eof, config) {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(e) => {
+                *self = Self::new(config);
+                return Err(e.locate(src, &Position::default()).into());
+            }
+        }
+
+        let head_len = self.head_len;
+        let decoding = std::mem::replace(self, Self::new(config));
+        let parsed = decoding
+            .partial
+            .framed(&src[head_len..], method)
+            .map(|(message, rest)| (message, src.len() - rest.len()));
+
+        complete(src, parsed)
+    }
+
+    fn advance(
+        &mut self,
+        src: &[u8],
+        method: &Method,
+        eof: bool,
+        config: &ParserConfig,
+    ) -> Result<bool, FromUtf8Err> {
+        if self.body.is_none() {
+            let fed = std::mem::replace(&mut self.fed, src.len());
+            if let Err(e) = self.partial.feed(&src[fed..]) {
+                return if e.is_incomplete() { Ok(false) } else { Err(e) };
+            }
+
+            self.head_len = src.len() - self.partial.rest().len();
+            self.body = Some(match self.partial.body_length(method)? {
+                BodyLength::NoBody => BodyState::Fixed(0),
+                BodyLength::Fixed(len) => BodyState::Fixed(len),
+                BodyLength::Chunked => BodyState::Chunked(Chunk::Size),
+                BodyLength::UntilClose => BodyState::UntilClose,
+            });
+        }
+
+        self.scan(&src[self.head_len..], eof, config)
+    }
+
+    fn scan(&mut self, body: &[u8], eof: bool, config: &ParserConfig) -> Result<bool, FromUtf8Err> {
+        loop {
+            let rest = &body[self.scanned..];
+            let chunk = match self.body {
+                Some(BodyState::Fixed(len)) => return Ok(body.len() >= len),
+                Some(BodyState::UntilClose) => return Ok(eof),
+                Some(BodyState::Chunked(chunk)) => chunk,
+                None => return Ok(false),
+            };
+
+            let (next, len) = match chunk {
+                Chunk::Size => match line_len(rest) {
+                    Some(len) => match chunk_size_line(&rest[..len]) {
+                        Some(0) => (Chunk::Trailers(self.scanned + len), len),
+                        Some(size) => (Chunk::Data(size), len),
+                        None => return Ok(true),
+                    },
+                    None if rest.len() > config.max_start_line_len => {
+                        let at = self.head_len + self.scanned;
+                        return Err(FromUtf8Err::init(rest, ErrorKind::Chunk).at(at));
+                    }
+                    None => return Ok(false),
+                },
+                Chunk::Data(size) => match size.min(rest.len()) {
+                    0 => return Ok(false),
+                    len if len == size => (Chunk::DataEnd, len),
+                    len => (Chunk::Data(size - len), len),
+                },
+                Chunk::DataEnd => match rest {
+                    [b'\r', b'\n', ..] => (Chunk::Size, 2),
+                    [] | [b'\r'] => return Ok(false),
+                    _ => return Ok(true),
+                },
+                Chunk::Trailers(start) => {
+                    let Some(len) = line_len(rest) else {
+                        return Ok(self.scanned + rest.len() - start > config.max_head_size);
+                    };
+                    if matches!(&rest[..len], b"\r\n" | b"\n")
+                        || self.scanned + len - start > config.max_head_size
+                    {
+                        return Ok(true);
+                    }
+
+                    (Chunk::Trailers(start), len)
+                }
+            };
+
+            self.body = Some(BodyState::Chunked(next));
+            self.scanned += len;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RequestCodec {
+    config: ParserConfig,
+    decoding: Decoding<PartialRequest>,
+}
+
+impl RequestCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            decoding: Decoding::new(&config),
+            config,
+        }
+    }
+}
+
+impl Decoder for RequestCodec {
+    type Item = Request<Vec<u8>>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decoding.decode(src, &Method::GET, false, &self.config)
+    }
+}
+
+impl Encoder<Response<Vec<u8>>> for RequestCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Response<Vec<u8>>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode(&item, dst)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCodec {
+    config: ParserConfig,
+    methods: VecDeque<Method>,
+    decoding: Decoding<PartialResponse>,
+}
+
+impl ResponseCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            decoding: Decoding::new(&config),
+            config,
+            methods: VecDeque::new(),
+        }
+    }
+
+    pub fn push_request_method(&mut self, method: Method) {
+        self.methods.push_back(method);
+    }
+
+    fn parse(
+        &mut self,
+        src: &mut BytesMut,
+        eof: bool,
+    ) -> Result<Option<Response<Vec<u8>>>, CodecError> {
+        let method = self.methods.front().cloned().unwrap_or(Method::GET);
+        let response = self.decoding.decode(src, &method, eof, &self.config)?;
+
+        Ok(self.answered(response))
+    }
+
+    fn answered(&mut self, response: Option<Response<Vec<u8>>>) -> Option<Response<Vec<u8>>> {
+        let status = response.as_ref()?.status();
+        if !status.is_informational() || status == StatusCode::SWITCHING_PROTOCOLS {
+            self.methods.pop_front();
+        }
+
+        response
+    }
+}
+
+impl Decoder for ResponseCodec {
+    type Item = Response<Vec<u8>>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.parse(src, false)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        match self.parse(src, true)? {
+            Some(response) => Ok(Some(response)),
+            None => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+        }
+    }
+}
+
+impl Encoder<Request<Vec<u8>>> for ResponseCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Request<Vec<u8>>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        encode(&item, dst)?;
+        self.methods.push_back(item.method().clone());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::{Framed, FramedRead};
+
+    use crate::FromUtf8Framed;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_duplex_round_trip() {
+        let (client, server) = tokio::io::duplex(16);
+        let mut client = Framed::new(client, ResponseCodec::new());
+        let mut server = Framed::new(server, RequestCodec::new());
+
+        let request = Request::post("/echo")
+            .header("content-length", "5")
+            .body(b"hello".to_vec())
+            .unwrap();
+
+        let (sent, received) = tokio::join!(client.send(request), server.next());
+        sent.unwrap();
+        let request = received.unwrap().unwrap();
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.body(), b"hello");
+
+        let response = Response::builder()
+            .header("transfer-encoding", "chunked")
+            .body(request.into_body())
+            .unwrap();
+
+        let (sent, received) = tokio::join!(server.send(response), client.next());
+        sent.unwrap();
+        let response = received.unwrap().unwrap();
+        assert_eq!(response.body(), b"hello");
+    }
+
+    #[tokio::test]
+    async fn test_pipelined_methods() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut client = Framed::new(client, ResponseCodec::new());
+
+        for method in [Method::HEAD, Method::GET] {
+            let request = Request::builder()
+                .method(method)
+                .uri("/")
+                .body(Vec::new())
+                .unwrap();
+            client.send(request).await.unwrap();
+        }
+
+        server
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello")
+            .await
+            .unwrap();
+
+        let statuses = [100, 200, 200];
+        let bodies: [&[u8]; 3] = [b"", b"", b"hello"];
+        for (status, body) in statuses.into_iter().zip(bodies) {
+            let response = client.next().await.unwrap().unwrap();
+            assert_eq!(response.status(), status);
+            assert_eq!(response.body(), body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_until_close() {
+        let (mut writer, reader) = tokio::io::duplex(256);
+        let mut responses = FramedRead::new(reader, ResponseCodec::new());
+
+        writer
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\nHTTP/1.1 200 OK\r\n\r\nstreamed")
+            .await
+            .unwrap();
+        writer.write_all(b" until close").await.unwrap();
+        drop(writer);
+
+        let response = responses.next().await.unwrap().unwrap();
+        assert_eq!(response.status(), 204);
+
+        let response = responses.next().await.unwrap().unwrap();
+        assert_eq!(response.body(), b"streamed until close");
+        assert!(responses.next().await.is_none());
+    }
+
+    #[test]
+    fn test_incremental_decode() {
+        let input = b"POST /a HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTrailer: Expires\r\n\r\n4\r\nWiki\r\n6;x=1\r\npedia \r\n0\r\nExpires: never\r\n\r\nPUT /b HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /c HTTP/1.1\r\n\r\n";
+
+        let mut codec = RequestCodec::new();
+        let mut src = BytesMut::new();
+        let mut requests = Vec::new();
+        for piece in input.chunks(3) {
+            src.extend_from_slice(piece);
+            while let Some(request) = codec.decode(&mut src).unwrap() {
+                requests.push(request);
+            }
+        }
+        assert!(src.is_empty());
+
+        let uris: Vec<_> = requests
+            .iter()
+            .map(|request| request.uri().path())
+            .collect();
+        assert_eq!(uris, ["/a", "/b", "/c"]);
+        assert_eq!(requests[0].body(), b"Wikipedia ");
+        assert_eq!(requests[1].body(), b"hello");
+
+        let input = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWikiX\r\n0\r\n\r\n";
+        let expected = Request::from_utf8_framed(input).unwrap_err();
+
+        let mut codec = RequestCodec::new();
+        let mut src = BytesMut::new();
+        let mut result = Ok(None);
+        for piece in input.chunks(2) {
+            src.extend_from_slice(piece);
+            result = codec.decode(&mut src);
+            if !matches!(result, Ok(None)) {
+                break;
+            }
+        }
+        let Err(CodecError::Parse(err)) = result else {
+            panic!("expected a parse error, got {result:?}");
+        };
+        assert_eq!(err.kind(), &ErrorKind::Chunk);
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (expected.offset(), expected.line(), expected.column())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parse_error() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let mut requests = FramedRead::new(reader, RequestCodec::new());
+
+        writer.write_all(b"GET /\r\n\r\n").await.unwrap();
+
+        let err = requests.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, CodecError::Parse(_)));
+    }
+}
@@ -0,0 +1,77 @@
+use http::header::{HeaderName, CONTENT_LENGTH, HOST};
+use http::Method;
+
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    pub duplicate_policy: DuplicatePolicy,
+    pub allowed_methods: Option<Vec<Method>>,
+    pub version_policy: VersionPolicy,
+    pub preserve_raw: bool,
+    pub strict_target_form: bool,
+    pub lenient: bool,
+    pub obs_fold: ObsFoldPolicy,
+    pub max_start_line_len: usize,
+    pub max_header_count: usize,
+    pub max_header_size: usize,
+    pub max_head_size: usize,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_policy: DuplicatePolicy::default(),
+            allowed_methods: None,
+            version_policy: VersionPolicy::default(),
+            preserve_raw: false,
+            strict_target_form: false,
+            lenient: false,
+            obs_fold: ObsFoldPolicy::default(),
+            max_start_line_len: 8 * 1024,
+            max_header_count: 100,
+            max_header_size: 8 * 1024,
+            max_head_size: 64 * 1024,
+        }
+    }
+}
+
+impl ParserConfig {
+    pub fn allows_method(&self, method: &Method) -> bool {
+        self.allowed_methods
+            .as_ref()
+            .is_none_or(|methods| methods.contains(method))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionPolicy {
+    #[default]
+    Reject,
+    DowngradeMinor,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ObsFoldPolicy {
+    #[default]
+    Reject,
+    Replace,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    #[default]
+    Append,
+    Reject(Vec<HeaderName>),
+}
+
+impl DuplicatePolicy {
+    pub fn reject_singletons() -> Self {
+        DuplicatePolicy::Reject(vec![HOST, CONTENT_LENGTH])
+    }
+
+    pub fn rejects(&self, name: &HeaderName) -> bool {
+        match self {
+            DuplicatePolicy::Append => false,
+            DuplicatePolicy::Reject(names) => names.contains(name),
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use http::header::HOST;
+use http::uri::{Authority, Parts, Scheme};
+use http::{HeaderMap, Uri};
+
+use crate::error::*;
+use crate::extensions::TargetForm;
+
+fn default_port(scheme: &Scheme) -> Option<u16> {
+    if scheme == &Scheme::HTTP {
+        Some(80)
+    } else if scheme == &Scheme::HTTPS {
+        Some(443)
+    } else {
+        None
+    }
+}
+
+fn same_authority(a: &Authority, b: &Authority, scheme: &Scheme) -> bool {
+    let default = default_port(scheme);
+
+    a.host().eq_ignore_ascii_case(b.host()) && a.port_u16().or(default) == b.port_u16().or(default)
+}
+
+fn host_authority(headers: &HeaderMap) -> Result<Option<Authority>, FromUtf8Err> {
+    let mut hosts = headers.get_all(HOST).iter();
+
+    let host = match (hosts.next(), hosts.next()) {
+        (None, _) => return Ok(None),
+        (Some(host), None) => host,
+        (Some(_), Some(duplicate)) => {
+            return Err(FromUtf8Err::init(duplicate.as_bytes(), ErrorKind::Host))
+        }
+    };
+
+    if host.as_bytes().contains(&b'@') {
+        return Err(FromUtf8Err::init(host.as_bytes(), ErrorKind::Host));
+    }
+
+    Authority::try_from(host.as_bytes())
+        .map(Some)
+        .map_err(|_| FromUtf8Err::init(host.as_bytes(), ErrorKind::Host))
+}
+
+pub fn effective_request_uri(
+    uri: &Uri,
+    headers: &HeaderMap,
+    scheme: Scheme,
+) -> Result<Uri, FromUtf8Err> {
+    let host = host_authority(headers)?;
+
+    let mut parts = Parts::default();
+    match TargetForm::of(uri) {
+        TargetForm::Absolute => {
+            let (target_scheme, target_authority) = match (uri.scheme(), uri.authority()) {
+                (Some(scheme), Some(authority)) => (scheme, authority),
+                _ => return Err(FromUtf8Err::init(uri.to_string(), ErrorKind::Uri)),
+            };
+
+            if let Some(host) = host {
+                if !same_authority(target_authority, &host, target_scheme) {
+                    return Err(FromUtf8Err::init(host.as_str(), ErrorKind::HostMismatch));
+                }
+            }
+
+            return Ok(uri.clone());
+        }
+        TargetForm::Authority => {
+            parts.authority = uri.authority().cloned();
+        }
+        TargetForm::Origin | TargetForm::Asterisk => {
+            let host = host.ok_or_else(|| FromUtf8Err::init(Vec::new(), ErrorKind::Host))?;
+            if host.host().is_empty() {
+                return Err(FromUtf8Err::init(host.as_str(), ErrorKind::Host));
+            }
+
+            parts.authority = Some(host);
+            if uri.path() != "*" {
+                parts.path_and_query = uri.path_and_query().cloned();
+            }
+        }
+    }
+
+    parts.scheme = Some(scheme);
+    if parts.path_and_query.is_none() {
+        parts.path_and_query = Some("/".parse().unwrap());
+    }
+
+    Uri::from_parts(parts).map_err(|_| FromUtf8Err::init(uri.to_string(), ErrorKind::Uri))
+}
+
+#[cfg(test)]
+mod test {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn headers(hosts: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for host in hosts {
+            headers.append(HOST, HeaderValue::from_static(host));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_effective_request_uri() {
+        let uri = Uri::from_static("/where?q=now");
+        let effective =
+            effective_request_uri(&uri, &headers(&["www.example.org:8080"]), Scheme::HTTP).unwrap();
+        assert_eq!(effective, "http://www.example.org:8080/where?q=now");
+
+        let uri = Uri::from_static("*");
+        let effective =
+            effective_request_uri(&uri, &headers(&["www.example.org"]), Scheme::HTTPS).unwrap();
+        assert_eq!(effective, "https://www.example.org/");
+
+        let uri = Uri::from_static("www.example.org:443");
+        let effective = effective_request_uri(&uri, &headers(&[]), Scheme::HTTPS).unwrap();
+        assert_eq!(effective, "https://www.example.org:443/");
+
+        let uri = Uri::from_static("http://WWW.example.org/pub");
+        let effective =
+            effective_request_uri(&uri, &headers(&["www.example.org:80"]), Scheme::HTTPS).unwrap();
+        assert_eq!(effective, uri);
+    }
+
+    #[test]
+    fn test_host_errors() {
+        let uri = Uri::from_static("/");
+
+        let err = effective_request_uri(&uri, &headers(&[]), Scheme::HTTP).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Host);
+
+        let err =
+            effective_request_uri(&uri, &headers(&["a.com", "b.com"]), Scheme::HTTP).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Host);
+        assert_eq!(err.input(), b"b.com");
+
+        let err = effective_request_uri(&uri, &headers(&["bad host"]), Scheme::HTTP).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Host);
+
+        let err =
+            effective_request_uri(&uri, &headers(&["user@evil.com"]), Scheme::HTTP).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Host);
+        assert_eq!(err.input(), b"user@evil.com");
+
+        let uri = Uri::from_static("http://example.com/");
+        let err = effective_request_uri(&uri, &headers(&["evil.com"]), Scheme::HTTP).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HostMismatch);
+        assert_eq!(err.input(), b"evil.com");
+    }
+}
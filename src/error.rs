@@ -1,26 +1,201 @@
 use std::fmt::Display;
 
+use http::StatusCode;
 use nom::error::Error;
-use nom::Err;
+use nom::{Err, Needed};
 
-#[derive(Debug)]
+const FOUND_LIMIT: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ErrorKind {
     Method,
+    MethodNotAllowed,
     Uri,
+    TargetForm,
     Version,
+    UnsupportedVersion,
     Header,
+    DuplicateHeader,
+    ObsFold,
+    Host,
+    HostMismatch,
     StatusCode,
+    ContentLength,
+    TransferEncoding,
+    Chunk,
+    Trailer,
+    StartLineTooLong,
+    TooManyHeaders,
+    HeaderTooLarge,
+    HeadTooLarge,
+    Incomplete(Needed),
+}
+
+impl ErrorKind {
+    pub fn expected(&self) -> &'static str {
+        match self {
+            ErrorKind::Method => "method token followed by SP",
+            ErrorKind::MethodNotAllowed => "allowed method",
+            ErrorKind::Uri => "request-target followed by SP",
+            ErrorKind::TargetForm => "request-target form allowed for the method",
+            ErrorKind::Version => "HTTP-version",
+            ErrorKind::UnsupportedVersion => "HTTP/0.9, HTTP/1.0 or HTTP/1.1",
+            ErrorKind::Header => "field-line or empty line",
+            ErrorKind::DuplicateHeader => "single occurrence of field",
+            ErrorKind::ObsFold => "field-line without obsolete line folding",
+            ErrorKind::Host => "single valid Host field",
+            ErrorKind::HostMismatch => "Host matching the request-target authority",
+            ErrorKind::StatusCode => "3-digit status-code, SP and reason-phrase",
+            ErrorKind::ContentLength => "single decimal Content-Length",
+            ErrorKind::TransferEncoding => "chunked as final transfer-coding",
+            ErrorKind::Chunk => "chunk-size, chunk-data and CRLF",
+            ErrorKind::Trailer => "trailer field declared in Trailer",
+            ErrorKind::StartLineTooLong => "start-line within max_start_line_len",
+            ErrorKind::TooManyHeaders => "at most max_header_count field-lines",
+            ErrorKind::HeaderTooLarge => "field-line within max_header_size",
+            ErrorKind::HeadTooLarge => "message head within max_head_size",
+            ErrorKind::Incomplete(_) => "more input",
+        }
+    }
+
+    pub fn status_code(&self) -> Option<StatusCode> {
+        match self {
+            ErrorKind::Incomplete(_) => None,
+            ErrorKind::StartLineTooLong => Some(StatusCode::URI_TOO_LONG),
+            ErrorKind::TooManyHeaders | ErrorKind::HeaderTooLarge | ErrorKind::HeadTooLarge => {
+                Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+            }
+            ErrorKind::MethodNotAllowed => Some(StatusCode::NOT_IMPLEMENTED),
+            ErrorKind::UnsupportedVersion => Some(StatusCode::HTTP_VERSION_NOT_SUPPORTED),
+            _ => Some(StatusCode::BAD_REQUEST),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+    line_prefix: Vec<u8>,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+            line_prefix: Vec::new(),
+        }
+    }
+}
+
+impl Position {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn advance(&mut self, consumed: &[u8]) {
+        self.offset += consumed.len();
+
+        match consumed.iter().rposition(|&c| c == b'\n') {
+            Some(last) => {
+                self.line += consumed.iter().filter(|&&c| c == b'\n').count();
+                self.line_prefix.clear();
+                self.line_prefix.extend_from_slice(&consumed[last + 1..]);
+            }
+            None => self.line_prefix.extend_from_slice(consumed),
+        }
+        self.column = self.line_prefix.len() + 1;
+    }
 }
 
 #[derive(Debug)]
 pub struct FromUtf8Err {
-    input: String,
+    input: Vec<u8>,
     kind: ErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+    source_line: Vec<u8>,
 }
 
 impl FromUtf8Err {
-    pub fn init(input: String, kind: ErrorKind) -> Self {
-        Self { input, kind }
+    pub fn init(input: impl Into<Vec<u8>>, kind: ErrorKind) -> Self {
+        let mut input = input.into();
+        input.truncate(FOUND_LIMIT);
+
+        Self {
+            input,
+            kind,
+            offset: 0,
+            line: 0,
+            column: 0,
+            source_line: Vec::new(),
+        }
+    }
+
+    pub(crate) fn at(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub(crate) fn shift(mut self, by: usize) -> Self {
+        if self.line == 0 {
+            self.offset += by;
+        }
+        self
+    }
+
+    pub(crate) fn locate(mut self, base: &[u8], start: &Position) -> Self {
+        if self.line != 0 {
+            return self;
+        }
+
+        let at = self.offset.min(base.len());
+        let mut position = start.clone();
+        position.advance(&base[..at]);
+
+        let line_end = base[at..]
+            .iter()
+            .position(|&c| c == b'\r' || c == b'\n')
+            .map_or(base.len(), |len| at + len);
+
+        self.offset = position.offset;
+        self.line = position.line;
+        self.column = position.column;
+        self.source_line = position.line_prefix;
+        self.source_line.extend_from_slice(&base[at..line_end]);
+        self
+    }
+
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn expected(&self) -> &'static str {
+        self.kind.expected()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.kind, ErrorKind::Incomplete(_))
     }
 }
 
@@ -32,29 +207,83 @@ impl std::error::Error for FromUtf8Err {
 
 impl Display for FromUtf8Err {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "Error from input: [\"{}\"] ErrorKind: [{:?}]",
-            self.input, self.kind
-        )
+        write!(f, "{:?} error", self.kind)?;
+        if self.line != 0 {
+            write!(
+                f,
+                " at line {}, column {} (byte {})",
+                self.line, self.column, self.offset
+            )?;
+        }
+
+        write!(f, ": expected {}, found ", self.expected())?;
+        if self.input.is_empty() {
+            write!(f, "end of input")?;
+        } else {
+            write!(
+                f,
+                "\"{}\"",
+                String::from_utf8_lossy(&self.input).escape_debug()
+            )?;
+        }
+
+        if self.line != 0 {
+            let gutter = " ".repeat(self.line.to_string().len());
+            let source_line = String::from_utf8_lossy(&self.source_line);
+
+            write!(f, "\n{} |", gutter)?;
+            write!(f, "\n{} | {}", self.line, source_line.escape_debug())?;
+            write!(f, "\n{} | {:>2$}", gutter, "^", self.column)?;
+        }
+
+        Ok(())
     }
 }
 
 pub trait IntoFromUtf8Err {
-    fn into_parse_error(self, kind: ErrorKind) -> FromUtf8Err;
+    fn into_parse_error(self, base: &[u8], kind: ErrorKind) -> FromUtf8Err;
 }
 
 impl IntoFromUtf8Err for Err<Error<&[u8]>> {
-    fn into_parse_error(self, kind: ErrorKind) -> FromUtf8Err {
+    fn into_parse_error(self, base: &[u8], kind: ErrorKind) -> FromUtf8Err {
         let error = match self {
-            Err::Incomplete(_) => unimplemented!(),
+            Err::Incomplete(needed) => {
+                return FromUtf8Err::init(Vec::new(), ErrorKind::Incomplete(needed)).at(base.len())
+            }
             Err::Error(e) => e,
             Err::Failure(e) => e,
         };
 
-        FromUtf8Err {
-            input: String::from_utf8(error.input.to_vec()).unwrap(),
-            kind,
-        }
+        let found = error
+            .input
+            .iter()
+            .position(|&c| c == b'\r' || c == b'\n')
+            .map_or(error.input, |len| &error.input[..len]);
+
+        FromUtf8Err::init(found, kind).at(base.len() - error.input.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let base = b"Host: example.com\r\nBad Header\r\n\r\n";
+        let mut start = Position::default();
+        start.advance(b"GET / HTTP/1.1\r\n");
+
+        let err = FromUtf8Err::init(&base[22..29], ErrorKind::Header)
+            .at(22)
+            .locate(base, &start);
+
+        assert_eq!(err.offset(), 38);
+        assert_eq!(err.line(), 3);
+        assert_eq!(err.column(), 4);
+        assert_eq!(
+            err.to_string(),
+            "Header error at line 3, column 4 (byte 38): expected field-line or empty line, found \" Header\"\n  |\n3 | Bad Header\n  |    ^"
+        );
     }
 }
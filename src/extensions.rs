@@ -0,0 +1,215 @@
+use http::header::LINK;
+use http::{HeaderMap, HeaderValue, Method, Response, StatusCode, Uri};
+
+use crate::chunked::chunked_message;
+use crate::config::ParserConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReasonPhrase {
+    status: StatusCode,
+    phrase: Vec<u8>,
+}
+
+impl ReasonPhrase {
+    pub fn new(status: StatusCode, phrase: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            phrase: phrase.into(),
+        }
+    }
+
+    pub fn canonical(status: StatusCode) -> Self {
+        Self::new(status, status.canonical_reason().unwrap_or_default())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.phrase
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trailers(HeaderMap);
+
+impl Trailers {
+    pub fn new(trailers: HeaderMap) -> Self {
+        Self(trailers)
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.0
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InterimResponses(Vec<Response<()>>);
+
+impl InterimResponses {
+    pub fn new(responses: Vec<Response<()>>) -> Self {
+        Self(responses)
+    }
+
+    pub fn responses(&self) -> &[Response<()>] {
+        &self.0
+    }
+
+    pub fn early_hints(&self) -> impl Iterator<Item = &HeaderValue> {
+        self.0
+            .iter()
+            .filter(|response| response.status().as_u16() == 103)
+            .flat_map(|response| response.headers().get_all(LINK))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetForm {
+    Origin,
+    Absolute,
+    Authority,
+    Asterisk,
+}
+
+impl TargetForm {
+    pub fn of(uri: &Uri) -> Self {
+        if uri.scheme().is_some() {
+            TargetForm::Absolute
+        } else if uri.authority().is_some() {
+            TargetForm::Authority
+        } else if uri.path() == "*" {
+            TargetForm::Asterisk
+        } else {
+            TargetForm::Origin
+        }
+    }
+
+    pub fn allows(&self, method: &Method) -> bool {
+        match self {
+            TargetForm::Authority => method == Method::CONNECT,
+            TargetForm::Asterisk => method == Method::OPTIONS,
+            TargetForm::Origin | TargetForm::Absolute => method != Method::CONNECT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeniencyKind {
+    BareLf,
+    ExtraWhitespace,
+    LeadingEmptyLines,
+    FieldNameWhitespace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leniency {
+    kind: LeniencyKind,
+    offset: usize,
+}
+
+impl Leniency {
+    pub(crate) fn new(kind: LeniencyKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    pub(crate) fn shift(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+
+    pub fn kind(&self) -> LeniencyKind {
+        self.kind
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Leniencies(Vec<Leniency>);
+
+impl Leniencies {
+    pub fn new(leniencies: Vec<Leniency>) -> Self {
+        Self(leniencies)
+    }
+
+    pub fn as_slice(&self) -> &[Leniency] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Leniency> {
+        self.0.iter()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawHead {
+    start_line: Vec<u8>,
+    canonical_start_line: Vec<u8>,
+    fields: Vec<Vec<u8>>,
+    headers: HeaderMap,
+    end: Vec<u8>,
+}
+
+impl RawHead {
+    pub fn start_line(&self) -> &[u8] {
+        &self.start_line
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &[u8]> {
+        self.fields.iter().map(|line| &line[..])
+    }
+
+    pub(crate) fn push_start_line(&mut self, consumed: &[u8]) {
+        self.start_line.extend_from_slice(consumed);
+    }
+
+    pub(crate) fn set_fields(&mut self, fields: Vec<&[u8]>, headers: &HeaderMap, end: &[u8]) {
+        self.fields = fields.into_iter().map(|line| line.to_vec()).collect();
+        self.headers = headers.clone();
+        self.end = end.to_vec();
+    }
+
+    pub(crate) fn set_canonical_start_line(&mut self, start_line: Vec<u8>) {
+        self.canonical_start_line = start_line;
+    }
+
+    pub(crate) fn matches(&self, start_line: &[u8], headers: &HeaderMap) -> bool {
+        self.canonical_start_line == start_line && &self.headers == headers
+    }
+
+    pub(crate) fn write_to(&self, result: &mut Vec<u8>) {
+        result.extend_from_slice(&self.start_line);
+        for line in &self.fields {
+            result.extend_from_slice(line);
+        }
+        result.extend_from_slice(&self.end);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawBody(Vec<u8>);
+
+impl RawBody {
+    pub fn new(raw: impl Into<Vec<u8>>) -> Self {
+        Self(raw.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub(crate) fn decodes_to(&self, body: &[u8], trailers: &HeaderMap) -> bool {
+        chunked_message(&self.0, &ParserConfig::default()).is_ok_and(
+            |(decoded, decoded_trailers, rest)| {
+                decoded == body && &decoded_trailers == trailers && rest.is_empty()
+            },
+        )
+    }
+}
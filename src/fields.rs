@@ -0,0 +1,304 @@
+use http::header::HeaderName;
+use http::{HeaderMap, HeaderValue};
+
+use nom::{bytes::streaming::tag, sequence::*, Needed, Offset};
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::config::*;
+use crate::error::*;
+use crate::extensions::{Leniency, LeniencyKind};
+use crate::http_combinator::*;
+use crate::lenient::{line_end, name_whitespace};
+
+pub type FieldLine<'a> = (&'a [u8], &'a [u8]);
+pub type ParsedFields<'a> = (&'a [u8], Vec<FieldLine<'a>>, Vec<Leniency>);
+
+pub fn field_lines(input: &[u8]) -> Result<(&[u8], Vec<FieldLine<'_>>), FromUtf8Err> {
+    let (rest, fields, _) = parse_field_lines(input, &ParserConfig::default())?;
+
+    Ok((rest, fields))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FieldProgress {
+    fields: Vec<(Range<usize>, Range<usize>)>,
+    leniencies: Vec<Leniency>,
+    parsed: usize,
+    lines: usize,
+}
+
+impl FieldProgress {
+    pub fn parsed(&self) -> usize {
+        self.parsed
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+}
+
+pub fn parse_field_lines<'a>(
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Result<ParsedFields<'a>, FromUtf8Err> {
+    resume_field_lines(input, &mut FieldProgress::default(), config)
+}
+
+pub fn resume_field_lines<'a>(
+    input: &'a [u8],
+    progress: &mut FieldProgress,
+    config: &ParserConfig,
+) -> Result<ParsedFields<'a>, FromUtf8Err> {
+    let lenient = config.lenient;
+    let mut rest = &input[progress.parsed..];
+
+    let bare_lf = |ending: &[u8]| {
+        (ending == b"\n").then(|| Leniency::new(LeniencyKind::BareLf, input.offset(ending)))
+    };
+    let range = |slice: &[u8]| input.offset(slice)..input.offset(slice) + slice.len();
+
+    loop {
+        if !rest.contains(&b'\n') {
+            let needed = ErrorKind::Incomplete(Needed::Unknown);
+            return Err(FromUtf8Err::init(Vec::new(), needed).at(input.len()));
+        }
+
+        match line_end(lenient)(rest) {
+            Ok((after, ending)) => {
+                let FieldProgress {
+                    fields,
+                    mut leniencies,
+                    ..
+                } = std::mem::take(progress);
+                leniencies.extend(bare_lf(ending));
+
+                let fields = fields
+                    .into_iter()
+                    .map(|(name, value)| (&input[name], &input[value]))
+                    .collect();
+                return Ok((after, fields, leniencies));
+            }
+            Err(nom::Err::Error(_)) => {}
+            Err(e) => return Err(e.into_parse_error(input, ErrorKind::Header)),
+        }
+
+        let folded = rest.first().is_some_and(|&c| c == b' ' || c == b'\t');
+        if let (Some((_, value)), true) = (progress.fields.last_mut(), folded) {
+            if config.obs_fold == ObsFoldPolicy::Reject {
+                return Err(FromUtf8Err::init(rest, ErrorKind::ObsFold).at(input.offset(rest)));
+            }
+
+            let (after, ((_, folded, _), ending)) =
+                tuple((tuple((rws, field_value, ows)), line_end(lenient)))(rest)
+                    .map_err(|e| e.into_parse_error(input, ErrorKind::Header))?;
+            progress.leniencies.extend(bare_lf(ending));
+
+            value.end = range(folded).end;
+            rest = after;
+        } else {
+            let (after, ((name, name_ws, _, _, value, _), ending)) = tuple((
+                tuple((
+                    field_name,
+                    name_whitespace(lenient),
+                    tag(":"),
+                    ows,
+                    field_value,
+                    ows,
+                )),
+                line_end(lenient),
+            ))(rest)
+            .map_err(|e| e.into_parse_error(input, ErrorKind::Header))?;
+
+            if !name_ws.is_empty() {
+                progress.leniencies.push(Leniency::new(
+                    LeniencyKind::FieldNameWhitespace,
+                    input.offset(name_ws),
+                ));
+            }
+            progress.leniencies.extend(bare_lf(ending));
+
+            progress.fields.push((range(name), range(value)));
+            rest = after;
+        }
+
+        progress.parsed = input.offset(rest);
+        progress.lines += 1;
+    }
+}
+
+pub fn unfold(value: &[u8]) -> Cow<'_, [u8]> {
+    if !value.contains(&b'\n') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut unfolded = Vec::with_capacity(value.len());
+    for line in value.split(|&c| c == b'\n') {
+        let line = line.trim_ascii();
+        if line.is_empty() {
+            continue;
+        }
+
+        if !unfolded.is_empty() {
+            unfolded.push(b' ');
+        }
+        unfolded.extend_from_slice(line);
+    }
+
+    Cow::Owned(unfolded)
+}
+
+pub fn raw_field_lines<'a>(
+    input: &'a [u8],
+    fields: &[FieldLine<'a>],
+    rest: &'a [u8],
+) -> (Vec<&'a [u8]>, &'a [u8]) {
+    let head_end = input.offset(rest);
+    let fields_end = if input[..head_end].ends_with(b"\r\n") {
+        head_end - 2
+    } else {
+        head_end - 1
+    };
+
+    let starts: Vec<_> = fields.iter().map(|(key, _)| input.offset(key)).collect();
+    let lines = starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| &input[start..starts.get(i + 1).copied().unwrap_or(fields_end)])
+        .collect();
+
+    (lines, &input[fields_end..head_end])
+}
+
+pub fn write_fields(result: &mut Vec<u8>, headers: &HeaderMap) {
+    for (name, value) in headers {
+        result.extend_from_slice(name.as_str().as_bytes());
+        result.extend_from_slice(b": ");
+        result.extend_from_slice(value.as_bytes());
+        result.extend_from_slice(b"\r\n");
+    }
+}
+
+pub fn header_map(
+    base: &[u8],
+    fields: &[FieldLine<'_>],
+    config: &ParserConfig,
+) -> Result<HeaderMap, FromUtf8Err> {
+    let mut header_map = HeaderMap::new();
+
+    for &(key, value) in fields {
+        let name = HeaderName::from_bytes(key)
+            .map_err(|_| FromUtf8Err::init(key, ErrorKind::Header).at(base.offset(key)))?;
+        let val = HeaderValue::from_bytes(&unfold(value))
+            .map_err(|_| FromUtf8Err::init(value, ErrorKind::Header).at(base.offset(value)))?;
+
+        if header_map.contains_key(&name) && config.duplicate_policy.rejects(&name) {
+            return Err(FromUtf8Err::init(key, ErrorKind::DuplicateHeader).at(base.offset(key)));
+        }
+
+        header_map.append(name, val);
+    }
+
+    Ok(header_map)
+}
+
+#[cfg(test)]
+mod test {
+    use http::header::{HOST, SET_COOKIE};
+
+    use super::*;
+
+    #[test]
+    fn test_repeated_fields() {
+        let input = b"Set-Cookie: a=1\r\nHost: example.com\r\nSet-Cookie: b=2\r\n\r\n";
+
+        let (rest, fields) = field_lines(input).unwrap();
+        assert!(rest.is_empty());
+        let headers = header_map(input, &fields, &ParserConfig::default()).unwrap();
+        let cookies: Vec<_> = headers.get_all(SET_COOKIE).iter().collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+    }
+
+    #[test]
+    fn test_raw_field_lines() {
+        let input = b"HOST:  example.com \r\nx-Custom:a\r\n\r\nbody";
+
+        let (rest, fields) = field_lines(input).unwrap();
+        let (lines, end) = raw_field_lines(input, &fields, rest);
+        assert_eq!(lines, [&b"HOST:  example.com \r\n"[..], b"x-Custom:a\r\n"]);
+        assert_eq!(end, b"\r\n");
+        assert_eq!(rest, b"body");
+    }
+
+    #[test]
+    fn test_lenient_field_lines() {
+        let lenient = ParserConfig {
+            lenient: true,
+            ..ParserConfig::default()
+        };
+        let input = b"Host : example.com\nAccept: */*\r\n\nbody";
+
+        let (rest, fields, leniencies) = parse_field_lines(input, &lenient).unwrap();
+        assert_eq!(rest, b"body");
+        assert_eq!(
+            fields,
+            [(&b"Host"[..], &b"example.com"[..]), (b"Accept", b"*/*")]
+        );
+
+        let leniencies: Vec<_> = leniencies.iter().map(|l| (l.kind(), l.offset())).collect();
+        assert_eq!(
+            leniencies,
+            [
+                (LeniencyKind::FieldNameWhitespace, 4),
+                (LeniencyKind::BareLf, 18),
+                (LeniencyKind::BareLf, 32),
+            ]
+        );
+
+        assert!(parse_field_lines(b"Host: a\r\n\r", &lenient)
+            .unwrap_err()
+            .is_incomplete());
+    }
+
+    #[test]
+    fn test_obs_fold() {
+        let input = b"X-Folded: first\r\n  second \r\n\tthird\r\nX-Empty:\r\n next\r\n\r\n";
+
+        let err = field_lines(input).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ObsFold);
+        assert_eq!(err.offset(), 17);
+
+        let config = ParserConfig {
+            obs_fold: ObsFoldPolicy::Replace,
+            ..ParserConfig::default()
+        };
+        let (rest, fields, _) = parse_field_lines(input, &config).unwrap();
+        assert!(rest.is_empty());
+
+        let headers = header_map(input, &fields, &config).unwrap();
+        assert_eq!(headers.get("x-folded").unwrap(), "first second third");
+        assert_eq!(headers.get("x-empty").unwrap(), "next");
+
+        let err = parse_field_lines(b"X-Folded: first\r\n  ", &config).unwrap_err();
+        assert!(err.is_incomplete());
+    }
+
+    #[test]
+    fn test_reject_singletons() {
+        let config = ParserConfig {
+            duplicate_policy: DuplicatePolicy::reject_singletons(),
+            ..ParserConfig::default()
+        };
+
+        let input = b"Set-Cookie: a=1\r\nSet-Cookie: b=2\r\nHost: example.com\r\n\r\n";
+        let (_, fields) = field_lines(input).unwrap();
+        let headers = header_map(input, &fields, &config).unwrap();
+        assert_eq!(headers.get(HOST).unwrap(), "example.com");
+
+        let input = b"Host: example.com\r\nHost: evil.com\r\n\r\n";
+        let (_, fields) = field_lines(input).unwrap();
+        let err = header_map(input, &fields, &config).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::DuplicateHeader);
+    }
+}
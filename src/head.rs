@@ -0,0 +1,229 @@
+use http::{Method, Request, Response, StatusCode, Uri};
+
+use nom::{bytes::streaming::*, character::streaming::crlf, sequence::*, Offset};
+
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::extensions::ReasonPhrase;
+use crate::fields::{field_lines, header_map, FieldLine};
+use crate::http_combinator::*;
+use crate::http_elements::version_from_bytes;
+
+pub struct RequestHead<'a> {
+    raw: &'a [u8],
+    method: &'a [u8],
+    target: &'a [u8],
+    version: &'a [u8],
+    headers: Vec<FieldLine<'a>>,
+}
+
+impl<'a> RequestHead<'a> {
+    pub fn parse(input: &'a [u8]) -> Result<(Self, &'a [u8]), FromUtf8Err> {
+        let locate = |e: FromUtf8Err| e.locate(input, &Position::default());
+
+        let (after_method, method) = terminated(method, tag(" "))(input)
+            .map_err(|e| locate(e.into_parse_error(input, ErrorKind::Method)))?;
+        let (after_target, target) = terminated(is_not(" \r\n"), tag(" "))(after_method)
+            .map_err(|e| locate(e.into_parse_error(input, ErrorKind::Uri)))?;
+        let (after_version, version) = terminated(http_version, crlf)(after_target)
+            .map_err(|e| locate(e.into_parse_error(input, ErrorKind::Version)))?;
+        let (rest, headers) =
+            field_lines(after_version).map_err(|e| locate(e.shift(input.offset(after_version))))?;
+
+        let head = RequestHead {
+            raw: &input[..input.offset(rest)],
+            method,
+            target,
+            version,
+            headers,
+        };
+
+        Ok((head, rest))
+    }
+
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    pub fn method(&self) -> &'a [u8] {
+        self.method
+    }
+
+    pub fn target(&self) -> &'a [u8] {
+        self.target
+    }
+
+    pub fn version(&self) -> &'a [u8] {
+        self.version
+    }
+
+    pub fn headers(&self) -> &[FieldLine<'a>] {
+        &self.headers
+    }
+
+    pub fn header(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.as_bytes()))
+            .map(|&(_, value)| value)
+    }
+
+    pub fn to_request<T>(&self, body: T) -> Result<Request<T>, FromUtf8Err> {
+        let config = ParserConfig::default();
+        let locate = |e: FromUtf8Err| e.locate(self.raw, &Position::default());
+
+        let method = Method::from_bytes(self.method).map_err(|_| {
+            locate(
+                FromUtf8Err::init(self.method, ErrorKind::Method).at(self.raw.offset(self.method)),
+            )
+        })?;
+        let uri = Uri::try_from(self.target).map_err(|_| {
+            locate(FromUtf8Err::init(self.target, ErrorKind::Uri).at(self.raw.offset(self.target)))
+        })?;
+        let version = version_from_bytes(self.version, config.version_policy)
+            .map_err(|e| locate(e.at(self.raw.offset(self.version))))?;
+        let headers = header_map(self.raw, &self.headers, &config).map_err(locate)?;
+
+        let mut request = Request::new(body);
+        *request.method_mut() = method;
+        *request.uri_mut() = uri;
+        *request.version_mut() = version;
+        *request.headers_mut() = headers;
+
+        Ok(request)
+    }
+}
+
+pub struct ResponseHead<'a> {
+    raw: &'a [u8],
+    version: &'a [u8],
+    status: &'a [u8],
+    reason: &'a [u8],
+    headers: Vec<FieldLine<'a>>,
+}
+
+impl<'a> ResponseHead<'a> {
+    pub fn parse(input: &'a [u8]) -> Result<(Self, &'a [u8]), FromUtf8Err> {
+        let locate = |e: FromUtf8Err| e.locate(input, &Position::default());
+
+        let (after_version, version) = terminated(http_version, tag(" "))(input)
+            .map_err(|e| locate(e.into_parse_error(input, ErrorKind::Version)))?;
+        let (after_status, (status, _sp, reason)) =
+            terminated(tuple((status_code, tag(" "), reason_phrase)), crlf)(after_version)
+                .map_err(|e| locate(e.into_parse_error(input, ErrorKind::StatusCode)))?;
+        let (rest, headers) =
+            field_lines(after_status).map_err(|e| locate(e.shift(input.offset(after_status))))?;
+
+        let head = ResponseHead {
+            raw: &input[..input.offset(rest)],
+            version,
+            status,
+            reason,
+            headers,
+        };
+
+        Ok((head, rest))
+    }
+
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    pub fn version(&self) -> &'a [u8] {
+        self.version
+    }
+
+    pub fn status(&self) -> &'a [u8] {
+        self.status
+    }
+
+    pub fn reason(&self) -> &'a [u8] {
+        self.reason
+    }
+
+    pub fn headers(&self) -> &[FieldLine<'a>] {
+        &self.headers
+    }
+
+    pub fn header(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name.as_bytes()))
+            .map(|&(_, value)| value)
+    }
+
+    pub fn to_response<T>(&self, body: T) -> Result<Response<T>, FromUtf8Err> {
+        let config = ParserConfig::default();
+        let locate = |e: FromUtf8Err| e.locate(self.raw, &Position::default());
+
+        let version = version_from_bytes(self.version, config.version_policy)
+            .map_err(|e| locate(e.at(self.raw.offset(self.version))))?;
+        let status = StatusCode::from_bytes(self.status).map_err(|_| {
+            locate(
+                FromUtf8Err::init(self.status, ErrorKind::StatusCode)
+                    .at(self.raw.offset(self.status)),
+            )
+        })?;
+        let headers = header_map(self.raw, &self.headers, &config).map_err(locate)?;
+
+        let mut response = Response::new(body);
+        *response.version_mut() = version;
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+            .extensions_mut()
+            .insert(ReasonPhrase::new(status, self.reason));
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::{Method, Version};
+
+    use super::*;
+
+    #[test]
+    fn test_request_head() {
+        let input = b"GET /hello.htm HTTP/1.1\r\nHost: example.com\r\nAccept: */*\r\n\r\nrest";
+
+        let (head, rest) = RequestHead::parse(input).unwrap();
+        assert_eq!(head.method(), b"GET");
+        assert_eq!(head.target(), b"/hello.htm");
+        assert_eq!(head.version(), b"HTTP/1.1");
+        assert_eq!(head.header("host"), Some(&b"example.com"[..]));
+        assert_eq!(head.headers().len(), 2);
+        assert_eq!(head.raw(), &input[..input.len() - 4]);
+        assert_eq!(head.method().as_ptr(), input.as_ptr());
+        assert_eq!(rest, b"rest");
+
+        let req = head.to_request(()).unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.uri(), "/hello.htm");
+        assert_eq!(req.headers().get("accept").unwrap(), "*/*");
+
+        let err = RequestHead::parse(b"GET /a HTTP/1.1\r\nHost example.com\r\n\r\n")
+            .err()
+            .unwrap();
+        assert_eq!((err.line(), err.column()), (2, 5));
+    }
+
+    #[test]
+    fn test_response_head() {
+        let input = b"HTTP/1.0 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+
+        let (head, rest) = ResponseHead::parse(input).unwrap();
+        assert_eq!(head.status(), b"404");
+        assert_eq!(head.reason(), b"Not Found");
+        assert!(rest.is_empty());
+
+        let res = head.to_response(()).unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.version(), Version::HTTP_10);
+        assert_eq!(
+            res.extensions().get::<ReasonPhrase>().unwrap().as_bytes(),
+            b"Not Found"
+        );
+    }
+}
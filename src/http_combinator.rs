@@ -1,22 +1,12 @@
 use nom::{
-    branch::*, bytes::complete::*, character::complete::*, combinator::*, multi::*, sequence::*,
+    branch::*, bytes::streaming::*, character::streaming::*, combinator::*, multi::*, sequence::*,
     IResult,
 };
 
 use crate::basic_combinator::*;
 
 pub fn method(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    alt((
-        tag("OPTIONS"),
-        tag("GET"),
-        tag("HEAD"),
-        tag("POST"),
-        tag("PUT"),
-        tag("DELETE"),
-        tag("TRACE"),
-        tag("CONNECT"),
-        tag("PATCH"),
-    ))(input)
+    token(input)
 }
 
 pub fn http_version(input: &[u8]) -> IResult<&[u8], &[u8]> {
@@ -27,7 +17,7 @@ pub fn http_version(input: &[u8]) -> IResult<&[u8], &[u8]> {
 }
 
 pub fn status_code(input: &[u8]) -> IResult<&[u8], &[u8]> {
-    map_parser(take(3usize), digit1)(input)
+    take_while_m_n(3, 3, |c: u8| c.is_ascii_digit())(input)
 }
 
 pub fn reason_phrase(input: &[u8]) -> IResult<&[u8], &[u8]> {
@@ -48,6 +38,50 @@ pub fn field_value(input: &[u8]) -> IResult<&[u8], &[u8]> {
     Ok((rest, &input[0..len]))
 }
 
+pub fn field_line(input: &[u8]) -> IResult<&[u8], (&[u8], &[u8])> {
+    terminated(
+        separated_pair(field_name, tuple((tag(":"), ows)), field_value),
+        ows,
+    )(input)
+}
+
+pub fn chunk_size(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    hex_digit1(input)
+}
+
+pub fn chunk_ext(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (rest, _) = many0(tuple((
+        ows,
+        tag(";"),
+        ows,
+        token,
+        opt(tuple((ows, tag("="), ows, alt((token, quoted_string))))),
+    )))(input)?;
+    let len = input.len() - rest.len();
+
+    Ok((rest, &input[0..len]))
+}
+
+pub fn quoted_string(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    let (rest, _) = delimited(
+        tag("\""),
+        many0(alt((
+            qdtext,
+            preceded(tag("\\"), alt((htab, tag(" "), vchar, obs_text))),
+        ))),
+        tag("\""),
+    )(input)?;
+    let len = input.len() - rest.len();
+
+    Ok((rest, &input[0..len]))
+}
+
+pub fn qdtext(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while_m_n(1, 1, |c: u8| {
+        matches!(c, b'\t' | b' ' | b'!' | b'#'..=b'[' | b']'..=b'~') || c >= 0x80
+    })(input)
+}
+
 pub fn ows(input: &[u8]) -> IResult<&[u8], &[u8]> {
     let (rest, _) = many0(alt((space1, htab)))(input)?;
     let len = input.len() - rest.len();
@@ -55,6 +89,14 @@ pub fn ows(input: &[u8]) -> IResult<&[u8], &[u8]> {
     Ok((rest, &input[0..len]))
 }
 
+pub fn rws(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    take_while1(|c: u8| c == b' ' || c == b'\t')(input)
+}
+
+pub fn eol(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    alt((crlf, tag("\n")))(input)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -70,6 +112,12 @@ mod test {
             method(b"POST http://www.w3.org/pub/WWW/TheProject.html HTTP/1.1").unwrap();
         assert_eq!(b"POST", mthd);
         assert_eq!(b" http://www.w3.org/pub/WWW/TheProject.html HTTP/1.1", rest);
+
+        let (rest, mthd) = method(b"PROPFIND /file HTTP/1.1").unwrap();
+        assert_eq!(b"PROPFIND", mthd);
+        assert_eq!(b" /file HTTP/1.1", rest);
+
+        assert!(method(b"(GET) / HTTP/1.1").is_err());
     }
 
     #[test]
@@ -100,6 +148,10 @@ mod test {
             field_value(b"Mozilla/4.0 (compatible; MSIE5.01; Windows NT)\r\n").unwrap();
         assert_eq!(b"Mozilla/4.0 (compatible; MSIE5.01; Windows NT)", val);
         assert_eq!(b"\r\n", rest);
+
+        let (rest, val) = field_value(b"caf\xe9 \xff\r\n").unwrap();
+        assert_eq!(b"caf\xe9 \xff", val);
+        assert_eq!(b"\r\n", rest);
     }
 
     #[test]
@@ -115,4 +167,35 @@ mod test {
         assert_eq!(b"Mozilla/4.0 (compatible; MSIE5.01; Windows NT)", value);
         assert_eq!(b"\r\n", rest);
     }
+
+    #[test]
+    fn test_chunk_ext() {
+        let (rest, ext) = chunk_ext(b"; name=value;quoted=\"a \\\"b\\\"\" ;flag\r\n").unwrap();
+        assert_eq!(b"; name=value;quoted=\"a \\\"b\\\"\" ;flag", ext);
+        assert_eq!(b"\r\n", rest);
+    }
+
+    #[test]
+    fn test_lenient_separators() {
+        assert_eq!(rws(b" \t /").unwrap(), (&b"/"[..], &b" \t "[..]));
+        assert!(rws(b"/").is_err());
+
+        assert_eq!(eol(b"\r\nHost").unwrap(), (&b"Host"[..], &b"\r\n"[..]));
+        assert_eq!(eol(b"\nHost").unwrap(), (&b"Host"[..], &b"\n"[..]));
+        assert!(matches!(eol(b"\r"), Err(nom::Err::Incomplete(_))));
+    }
+
+    #[test]
+    fn test_incomplete() {
+        assert!(matches!(method(b"GE"), Err(nom::Err::Incomplete(_))));
+        assert!(matches!(
+            http_version(b"HTTP/1"),
+            Err(nom::Err::Incomplete(_))
+        ));
+        assert!(matches!(status_code(b"20"), Err(nom::Err::Incomplete(_))));
+        assert!(matches!(
+            field_line(b"Host: www.exam"),
+            Err(nom::Err::Incomplete(_))
+        ));
+    }
 }
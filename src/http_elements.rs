@@ -1,14 +1,191 @@
-use http::{HeaderMap, StatusCode, Version};
+use http::{Extensions, HeaderMap, Method, StatusCode, Uri, Version};
 
-use crate::{FromUtf8, FromUtf8Err, IntoUtf8};
+use std::borrow::Cow;
+use std::io::{self, IoSlice, Write};
+
+use crate::body::is_chunked;
+use crate::chunked::{chunk_header, last_chunk};
+use crate::config::VersionPolicy;
+use crate::error::*;
+use crate::extensions::{RawBody, RawHead, ReasonPhrase, Trailers};
+use crate::fields::write_fields;
+use crate::IntoUtf8;
+
+pub fn version_from_bytes(
+    http_version: &[u8],
+    policy: VersionPolicy,
+) -> Result<Version, FromUtf8Err> {
+    Ok(match http_version {
+        b"HTTP/0.9" => Version::HTTP_09,
+        b"HTTP/1.0" => Version::HTTP_10,
+        b"HTTP/1.1" => Version::HTTP_11,
+        [b'H', b'T', b'T', b'P', b'/', b'1', b'.', minor]
+            if policy == VersionPolicy::DowngradeMinor && minor.is_ascii_digit() =>
+        {
+            Version::HTTP_11
+        }
+        _ => {
+            return Err(FromUtf8Err::init(
+                http_version,
+                ErrorKind::UnsupportedVersion,
+            ))
+        }
+    })
+}
+
+pub fn request_line(method: &Method, uri: &Uri, version: Version) -> Result<Vec<u8>, ()> {
+    let mut result = Vec::new();
+    result.extend_from_slice(method.as_str().as_bytes());
+    result.push(b' ');
+
+    result.extend_from_slice(uri.to_string().as_bytes());
+    result.push(b' ');
+
+    result.append(&mut version.into_utf8()?);
+
+    Ok(result)
+}
+
+pub fn status_line(
+    version: Version,
+    status: StatusCode,
+    reason: Option<&ReasonPhrase>,
+) -> Result<Vec<u8>, ()> {
+    let mut result = Vec::new();
+    result.append(&mut version.into_utf8()?);
+    result.push(b' ');
+
+    result.extend_from_slice(status.as_str().as_bytes());
+    result.push(b' ');
+
+    match reason {
+        Some(reason) if reason.status() == status => result.extend_from_slice(reason.as_bytes()),
+        _ => result.extend_from_slice(ReasonPhrase::canonical(status).as_bytes()),
+    }
+
+    Ok(result)
+}
+
+pub fn message_slices<'a>(
+    start_line: Vec<u8>,
+    headers: &HeaderMap,
+    extensions: &'a Extensions,
+    body: &'a [u8],
+) -> IoSlices<'a> {
+    let mut head = Vec::new();
+    match extensions.get::<RawHead>() {
+        Some(raw) if raw.matches(&start_line, headers) => raw.write_to(&mut head),
+        _ => {
+            head = start_line;
+            head.extend_from_slice(b"\r\n");
+
+            write_fields(&mut head, headers);
+            head.extend_from_slice(b"\r\n");
+        }
+    }
+
+    let mut slices = IoSlices::default();
+    if !is_chunked(headers) {
+        slices.push(head);
+        slices.push(body);
+        return slices;
+    }
+
+    let no_trailers = HeaderMap::new();
+    let trailers = extensions
+        .get::<Trailers>()
+        .map_or(&no_trailers, Trailers::headers);
+
+    match extensions.get::<RawBody>() {
+        Some(raw) if raw.decodes_to(body, trailers) => {
+            slices.push(head);
+            slices.push(raw.as_bytes());
+        }
+        _ if body.is_empty() => {
+            head.append(&mut last_chunk(trailers));
+            slices.push(head);
+        }
+        _ => {
+            head.append(&mut chunk_header(body.len()));
+            slices.push(head);
+            slices.push(body);
+
+            let mut tail = b"\r\n".to_vec();
+            tail.append(&mut last_chunk(trailers));
+            slices.push(tail);
+        }
+    }
+
+    slices
+}
+
+#[derive(Debug, Default)]
+pub struct IoSlices<'a> {
+    parts: Vec<Cow<'a, [u8]>>,
+}
+
+impl<'a> IoSlices<'a> {
+    fn push(&mut self, part: impl Into<Cow<'a, [u8]>>) {
+        let part = part.into();
+        if !part.is_empty() {
+            self.parts.push(part);
+        }
+    }
+
+    pub fn as_io_slices(&self) -> Vec<IoSlice<'_>> {
+        self.parts.iter().map(|part| IoSlice::new(part)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(|part| part.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.parts.concat()
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let mut slices = self.as_io_slices();
+        let mut slices = &mut slices[..];
+
+        while !slices.is_empty() {
+            match w.write_vectored(slices) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => IoSlice::advance_slices(&mut slices, written),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl IntoUtf8 for Method {
+    fn into_utf8(&self) -> Result<Vec<u8>, ()> {
+        Ok(self.as_str().as_bytes().to_vec())
+    }
+}
+
+impl IntoUtf8 for Uri {
+    fn into_utf8(&self) -> Result<Vec<u8>, ()> {
+        Ok(self.to_string().into_bytes())
+    }
+}
 
 impl IntoUtf8 for Version {
     fn into_utf8(&self) -> Result<Vec<u8>, ()> {
-        Ok(match self {
-            &Version::HTTP_09 => b"HTTP/0.9",
-            &Version::HTTP_10 => b"HTTP/1.0",
-            &Version::HTTP_11 => b"HTTP/1.1",
-            _ => unreachable!(),
+        Ok(match *self {
+            Version::HTTP_09 => b"HTTP/0.9",
+            Version::HTTP_10 => b"HTTP/1.0",
+            Version::HTTP_11 => b"HTTP/1.1",
+            Version::HTTP_2 => b"HTTP/2.0",
+            Version::HTTP_3 => b"HTTP/3.0",
+            _ => return Err(()),
         }
         .to_vec())
     }
@@ -16,21 +193,47 @@ impl IntoUtf8 for Version {
 
 impl IntoUtf8 for StatusCode {
     fn into_utf8(&self) -> Result<Vec<u8>, ()> {
-        Ok(self.to_string().as_bytes().to_vec())
+        Ok(self.as_str().as_bytes().to_vec())
     }
 }
 
 impl IntoUtf8 for HeaderMap {
     fn into_utf8(&self) -> Result<Vec<u8>, ()> {
         let mut result = Vec::new();
+        write_fields(&mut result, self);
 
-        for (key, value) in self.iter() {
-            result.append(&mut key.as_str().as_bytes().to_vec());
-            result.append(&mut b": ".to_vec());
-            result.append(&mut value.as_bytes().to_vec());
-            result.append(&mut b"\r\n".to_vec());
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_version_from_bytes() {
+        for version in [Version::HTTP_09, Version::HTTP_10, Version::HTTP_11] {
+            let bytes = version.into_utf8().unwrap();
+            assert_eq!(
+                version_from_bytes(&bytes, VersionPolicy::Reject).unwrap(),
+                version
+            );
         }
 
-        Ok(result)
+        for version in [Version::HTTP_2, Version::HTTP_3] {
+            let bytes = version.into_utf8().unwrap();
+            for policy in [VersionPolicy::Reject, VersionPolicy::DowngradeMinor] {
+                let err = version_from_bytes(&bytes, policy).unwrap_err();
+                assert_eq!(err.kind(), &ErrorKind::UnsupportedVersion);
+            }
+        }
+
+        let err = version_from_bytes(b"HTTP/1.2", VersionPolicy::Reject).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::UnsupportedVersion);
+        assert_eq!(
+            version_from_bytes(b"HTTP/1.2", VersionPolicy::DowngradeMinor).unwrap(),
+            Version::HTTP_11
+        );
+        assert!(version_from_bytes(b"HTTP/233.12", VersionPolicy::DowngradeMinor).is_err());
     }
 }
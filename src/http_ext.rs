@@ -1,11 +1,32 @@
+use std::io::{self, Write};
+
+use crate::http_elements::IoSlices;
 use crate::FromUtf8Err;
 
 pub trait FromUtf8<T> {
-    fn from_utf8<'a>(buf: &'a [u8], body: T) -> Result<Self, FromUtf8Err>
+    fn from_utf8(buf: &[u8], body: T) -> Result<Self, FromUtf8Err>
+    where
+        Self: Sized;
+}
+
+pub trait FromUtf8Framed {
+    fn from_utf8_framed(buf: &[u8]) -> Result<(Self, &[u8]), FromUtf8Err>
     where
         Self: Sized;
 }
 
+#[allow(clippy::wrong_self_convention, clippy::result_unit_err)]
 pub trait IntoUtf8 {
     fn into_utf8(&self) -> Result<Vec<u8>, ()>;
 }
+
+#[allow(clippy::result_unit_err)]
+pub trait WriteUtf8 {
+    fn to_io_slices(&self) -> Result<IoSlices<'_>, ()>;
+
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        self.to_io_slices()
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?
+            .write_to(w)
+    }
+}
@@ -0,0 +1,44 @@
+use nom::{bytes::streaming::tag, character::streaming::crlf, IResult, Offset};
+
+use crate::extensions::{Leniency, LeniencyKind};
+use crate::http_combinator::{eol, ows, rws};
+
+pub fn separator(lenient: bool) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |input| {
+        if lenient {
+            rws(input)
+        } else {
+            tag(" ")(input)
+        }
+    }
+}
+
+pub fn line_end(lenient: bool) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |input| {
+        if lenient {
+            eol(input)
+        } else {
+            crlf(input)
+        }
+    }
+}
+
+pub fn name_whitespace(lenient: bool) -> impl Fn(&[u8]) -> IResult<&[u8], &[u8]> {
+    move |input| {
+        if lenient {
+            ows(input)
+        } else {
+            Ok((input, &input[..0]))
+        }
+    }
+}
+
+pub fn leniency(base: &[u8], delimiter: &[u8], position: usize) -> Option<Leniency> {
+    let kind = match delimiter {
+        b" " | b"\r\n" => return None,
+        b"\n" => LeniencyKind::BareLf,
+        _ => LeniencyKind::ExtraWhitespace,
+    };
+
+    Some(Leniency::new(kind, position + base.offset(delimiter)))
+}
@@ -1,13 +1,99 @@
 mod basic_combinator;
+mod body;
+mod chunked;
+#[cfg(feature = "tokio")]
+mod codec;
+mod config;
+mod effective_uri;
 mod error;
+mod extensions;
+mod fields;
+mod head;
 mod http_elements;
 mod http_ext;
+mod lenient;
+mod limits;
 mod partial_request;
 mod partial_response;
+mod pipeline;
+mod reader;
 mod request;
 mod response;
+mod smuggling;
 
 pub mod http_combinator;
-pub use crate::error::FromUtf8Err;
-pub use crate::http_ext::{FromUtf8, IntoUtf8};
+pub use crate::body::{response_body_length_for, BodyLength};
+pub use crate::chunked::{
+    decode_chunked, decode_chunked_with_trailers, encode_chunked, encode_chunked_with_trailers,
+};
+#[cfg(feature = "tokio")]
+pub use crate::codec::{CodecError, RequestCodec, ResponseCodec};
+pub use crate::config::{DuplicatePolicy, ObsFoldPolicy, ParserConfig, VersionPolicy};
+pub use crate::effective_uri::effective_request_uri;
+pub use crate::error::{ErrorKind, FromUtf8Err};
+pub use crate::extensions::{
+    InterimResponses, Leniencies, Leniency, LeniencyKind, RawBody, RawHead, ReasonPhrase,
+    TargetForm, Trailers,
+};
+pub use crate::head::{RequestHead, ResponseHead};
+pub use crate::http_elements::IoSlices;
+pub use crate::http_ext::{FromUtf8, FromUtf8Framed, IntoUtf8, WriteUtf8};
 pub use crate::partial_request::PartialRequest;
+pub use crate::partial_response::PartialResponse;
+pub use crate::pipeline::{Pipeline, Pipelined};
+pub use crate::reader::{BodyReader, MessageReader};
+pub use crate::smuggling::{validate_request, validate_response, Finding, FindingKind};
+
+#[cfg(test)]
+mod test {
+    use http::{Request, Response};
+
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_bytes_do_not_panic() {
+        let seeds: [&[u8]; 3] = [
+            b"GET /hello.htm HTTP/1.1\r\nHost: example.com\r\nContent-Length: 4\r\n\r\nbody",
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;a=\"b\"\r\nbody\r\n0\r\nX: y\r\n\r\n",
+            b"POST * HTTP/1.0\r\nX-Obs: \xc0\xff\r\n\r\n",
+        ];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        for _ in 0..20_000 {
+            let mut input = seeds[next() % seeds.len()].to_vec();
+            for _ in 0..next() % 8 {
+                let at = next() % input.len();
+                input[at] = next() as u8;
+            }
+            input.truncate(next() % (input.len() + 1));
+
+            let _ = Request::<Vec<u8>>::from_utf8_framed(&input);
+            let _ = Response::<Vec<u8>>::from_utf8_framed(&input);
+            let _ = RequestHead::parse(&input).and_then(|(head, _)| head.to_request(()));
+            let _ = ResponseHead::parse(&input).and_then(|(head, _)| head.to_response(()));
+            let _ = validate_request(&input);
+            let _ = validate_response(&input);
+
+            let lenient = ParserConfig {
+                lenient: true,
+                ..ParserConfig::default()
+            };
+            let mut request = PartialRequest::new();
+            let mut response = PartialResponse::new();
+            let mut lenient_request = PartialRequest::with_config(lenient.clone());
+            let mut lenient_response = PartialResponse::with_config(lenient);
+            for chunk in input.chunks(next() % 5 + 1) {
+                let _ = request.feed(chunk).map_err(|e| e.to_string());
+                let _ = response.feed(chunk).map_err(|e| e.to_string());
+                let _ = lenient_request.feed(chunk);
+                let _ = lenient_response.feed(chunk);
+            }
+        }
+    }
+}
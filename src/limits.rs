@@ -0,0 +1,163 @@
+use crate::config::ParserConfig;
+use crate::error::*;
+
+fn line_len(input: &[u8]) -> usize {
+    input
+        .iter()
+        .position(|&c| c == b'\r' || c == b'\n')
+        .unwrap_or(input.len())
+}
+
+fn terminator_len(input: &[u8]) -> usize {
+    if input.starts_with(b"\r\n") {
+        2
+    } else {
+        input.len().min(1)
+    }
+}
+
+pub fn check_start_line(
+    input: &[u8],
+    consumed: usize,
+    config: &ParserConfig,
+) -> Result<(), FromUtf8Err> {
+    if consumed + line_len(input) > config.max_start_line_len {
+        let at = config.max_start_line_len.saturating_sub(consumed);
+        return Err(FromUtf8Err::init(&input[at..], ErrorKind::StartLineTooLong).at(at));
+    }
+    if consumed + line_len(input) > config.max_head_size {
+        let at = config.max_head_size.saturating_sub(consumed);
+        return Err(FromUtf8Err::init(&input[at..], ErrorKind::HeadTooLarge).at(at));
+    }
+
+    Ok(())
+}
+
+pub fn check_empty_lines(input: &[u8], config: &ParserConfig) -> Result<(), FromUtf8Err> {
+    let limit = config.max_start_line_len.min(config.max_head_size);
+    let skipped = input
+        .iter()
+        .take_while(|&&c| c == b'\r' || c == b'\n')
+        .count();
+
+    if skipped > limit {
+        return Err(FromUtf8Err::init(&input[limit..], ErrorKind::HeadTooLarge).at(limit));
+    }
+
+    Ok(())
+}
+
+pub fn check_fields(
+    input: &[u8],
+    consumed: usize,
+    lines: usize,
+    config: &ParserConfig,
+) -> Result<(), FromUtf8Err> {
+    let mut start = 0;
+    let mut count = lines;
+
+    while start < input.len() {
+        let len = line_len(&input[start..]);
+        if len == 0 {
+            break;
+        }
+
+        count += 1;
+        if count > config.max_header_count {
+            return Err(FromUtf8Err::init(&input[start..], ErrorKind::TooManyHeaders).at(start));
+        }
+        if len > config.max_header_size {
+            return Err(FromUtf8Err::init(&input[start..], ErrorKind::HeaderTooLarge).at(start));
+        }
+        if consumed + start + len > config.max_head_size {
+            let at = config.max_head_size.saturating_sub(consumed);
+            return Err(FromUtf8Err::init(&input[at..], ErrorKind::HeadTooLarge).at(at));
+        }
+
+        start += len;
+        start += terminator_len(&input[start..]);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> ParserConfig {
+        ParserConfig {
+            max_start_line_len: 16,
+            max_header_count: 2,
+            max_header_size: 12,
+            max_head_size: 40,
+            ..ParserConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_check_start_line() {
+        assert!(check_start_line(b"GET / HTTP/1.1\r\n", 0, &config()).is_ok());
+        assert!(check_start_line(b"/a HTTP/1.1", 4, &config()).is_ok());
+
+        let err = check_start_line(b"/longer HTTP/1.1", 4, &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::StartLineTooLong);
+        assert_eq!(err.offset(), 12);
+
+        let config = ParserConfig {
+            max_head_size: 12,
+            ..config()
+        };
+        let err = check_start_line(b"/a HTTP/1.1", 4, &config).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeadTooLarge);
+        assert_eq!(err.offset(), 8);
+    }
+
+    #[test]
+    fn test_check_empty_lines() {
+        assert!(check_empty_lines(b"\r\n\nGET", &config()).is_ok());
+        assert!(check_empty_lines(&b"\r\n".repeat(8), &config()).is_ok());
+
+        let err = check_empty_lines(&b"\r\n".repeat(9), &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeadTooLarge);
+        assert_eq!(err.offset(), 16);
+    }
+
+    #[test]
+    fn test_check_fields() {
+        assert!(check_fields(b"A: 1\r\nB: 2\r\n\r\nC: 3\r\nD: 4", 16, 0, &config()).is_ok());
+        assert!(check_fields(b"A: 1\r\nB: 2", 16, 0, &config()).is_ok());
+
+        let err = check_fields(b"A: 1\r\nB: 2\r\nC", 16, 0, &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooManyHeaders);
+        assert_eq!(err.offset(), 12);
+
+        let err = check_fields(b"C: 3", 28, 2, &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooManyHeaders);
+        assert_eq!(err.offset(), 0);
+
+        let err = check_fields(b"Cookie: abcdef", 16, 0, &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeaderTooLarge);
+        assert_eq!(err.offset(), 0);
+
+        let err = check_fields(b"A: 1\r\nB: 123456789", 30, 0, &config()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeadTooLarge);
+        assert_eq!(err.offset(), 10);
+    }
+
+    #[test]
+    fn test_status_code() {
+        assert_eq!(
+            ErrorKind::StartLineTooLong.status_code().unwrap().as_u16(),
+            414
+        );
+        assert_eq!(
+            ErrorKind::TooManyHeaders.status_code().unwrap().as_u16(),
+            431
+        );
+        assert_eq!(ErrorKind::HeadTooLarge.status_code().unwrap().as_u16(), 431);
+        assert!(ErrorKind::Incomplete(nom::Needed::Unknown)
+            .status_code()
+            .is_none());
+    }
+}
@@ -1,13 +1,21 @@
-use http::header::HeaderName;
-use http::{HeaderMap, HeaderValue, Method, Request, Uri, Version};
+use http::{HeaderMap, Method, Request, Uri, Version};
 
-use nom::{bytes::complete::*, multi::*, sequence::*};
+use nom::{bytes::streaming::*, combinator::recognize, multi::many0, sequence::*, Needed, Offset};
 
 use std::marker::PhantomData;
 
+use crate::body::*;
+use crate::chunked::BodyWithTrailers;
+use crate::config::ParserConfig;
 use crate::error::*;
+use crate::extensions::{
+    Leniencies, Leniency, LeniencyKind, RawBody, RawHead, TargetForm, Trailers,
+};
+use crate::fields::{header_map, raw_field_lines, resume_field_lines, FieldProgress};
 use crate::http_combinator::*;
-use crate::FromUtf8;
+use crate::http_elements::{request_line, version_from_bytes};
+use crate::lenient::{leniency, line_end, separator};
+use crate::limits::{check_empty_lines, check_fields, check_start_line};
 
 pub struct NeedMethod;
 pub struct NeedUri;
@@ -15,53 +23,135 @@ pub struct NeedVersion;
 pub struct NeedHeader;
 pub struct NeedBody;
 
+#[derive(Debug, Clone, Default)]
 pub struct PartialRequest {
     method: Option<Method>,
     uri: Option<Uri>,
     version: Option<Version>,
     headers: Option<HeaderMap>,
     rest: Vec<u8>,
+    position: Position,
+    head_start: usize,
+    config: ParserConfig,
+    raw: Option<RawHead>,
+    leniencies: Vec<Leniency>,
+    field_progress: FieldProgress,
+    framing: FramingFields,
 }
 
 impl PartialRequest {
-    pub fn builder(input: &[u8]) -> Builder<NeedMethod> {
-        let result = PartialRequest {
-            method: None,
-            uri: None,
-            version: None,
-            headers: None,
-            rest: Vec::new(),
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            raw: config.preserve_raw.then(RawHead::default),
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn builder(input: &[u8]) -> Builder<'_, NeedMethod> {
+        Builder::init(input, PartialRequest::new())
+    }
+
+    pub fn builder_with_config(input: &[u8], config: ParserConfig) -> Builder<'_, NeedMethod> {
+        Builder::init(input, PartialRequest::with_config(config))
+    }
+
+    pub(crate) fn builder_at(
+        input: &[u8],
+        config: ParserConfig,
+        position: Position,
+    ) -> Builder<'_, NeedMethod> {
+        let partial = PartialRequest {
+            head_start: position.offset(),
+            position,
+            ..PartialRequest::with_config(config)
         };
-        Builder::init(input, result)
+
+        Builder::init(input, partial)
+    }
+
+    pub fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        let mut buf = std::mem::take(&mut self.rest);
+        buf.extend_from_slice(input);
+
+        let mut rest = &buf[..];
+        let result = self.advance(&mut rest);
+        let consumed = buf.len() - rest.len();
+
+        buf.drain(..consumed);
+        self.rest = buf;
+
+        result
     }
 
     pub fn parse_rest<T>(mut self, body: T) -> Result<Request<T>, FromUtf8Err> {
-        let mut buf = Vec::new();
-        std::mem::swap(&mut buf, &mut self.rest);
-
-        let result = if self.headers.is_some() {
-            Builder::<NeedBody>::init(&buf, self)
-        } else {
-            if self.version.is_some() {
-                Builder::<NeedHeader>::init(&buf, self)
+        let buf = std::mem::take(&mut self.rest);
+
+        let mut rest = &buf[..];
+        self.advance(&mut rest)?;
+
+        Ok(Builder::<NeedBody>::init(rest, self).body(body))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn framed(mut self, input: &[u8]) -> Result<(Request<Vec<u8>>, &[u8]), FromUtf8Err> {
+        self.rest.clear();
+
+        Builder::<NeedBody>::init(input, self).framed()
+    }
+
+    fn advance(&mut self, input: &mut &[u8]) -> Result<(), FromUtf8Err> {
+        if self.method.is_none() {
+            self.step(input, Self::parse_method)?;
+        }
+        if self.uri.is_none() {
+            self.step(input, Self::parse_uri)?;
+        }
+        if self.version.is_none() {
+            self.step(input, Self::parse_version)?;
+        }
+        if self.headers.is_none() {
+            self.step(input, Self::parse_headers)?;
+        }
+
+        Ok(())
+    }
+
+    fn head_offset(&self) -> usize {
+        self.position.offset() - self.head_start
+    }
+
+    fn step<'a>(
+        &mut self,
+        input: &mut &'a [u8],
+        parse: fn(&mut Self, &'a [u8]) -> Result<&'a [u8], FromUtf8Err>,
+    ) -> Result<(), FromUtf8Err> {
+        let rest = parse(self, input).map_err(|e| {
+            if e.is_incomplete() {
+                e
             } else {
-                if self.uri.is_some() {
-                    Builder::<NeedVersion>::init(&buf, self)
-                } else {
-                    if self.method.is_some() {
-                        Builder::<NeedUri>::init(&buf, self)
-                    } else {
-                        Builder::<NeedMethod>::init(&buf, self).method()?
-                    }
-                    .uri()?
-                }
-                .version()?
+                e.locate(input, &self.position)
+            }
+        })?;
+        let consumed = &input[..input.len() - rest.len()];
+
+        if self.headers.is_none() {
+            if let Some(raw) = self.raw.as_mut() {
+                raw.push_start_line(consumed);
             }
-            .headers()?
         }
-        .body(body);
+        self.position.advance(consumed);
+        *input = rest;
+
+        Ok(())
+    }
 
-        Ok(result)
+    pub fn is_complete(&self) -> bool {
+        self.headers.is_some()
     }
 
     pub fn method(&self) -> &Option<Method> {
@@ -72,6 +162,10 @@ impl PartialRequest {
         &self.uri
     }
 
+    pub fn target_form(&self) -> Option<TargetForm> {
+        self.uri.as_ref().map(TargetForm::of)
+    }
+
     pub fn version(&self) -> &Option<Version> {
         &self.version
     }
@@ -79,6 +173,143 @@ impl PartialRequest {
     pub fn headers(&self) -> &Option<HeaderMap> {
         &self.headers
     }
+
+    pub fn rest(&self) -> &[u8] {
+        &self.rest
+    }
+
+    pub fn leniencies(&self) -> &[Leniency] {
+        &self.leniencies
+    }
+
+    pub fn raw(&self) -> &Option<RawHead> {
+        &self.raw
+    }
+
+    pub fn body_length(&self) -> Result<BodyLength, FromUtf8Err> {
+        let headers = self
+            .headers
+            .as_ref()
+            .ok_or_else(|| FromUtf8Err::init(Vec::new(), ErrorKind::Incomplete(Needed::Unknown)))?;
+
+        request_body_length(headers).map_err(|e| self.framing.locate(e))
+    }
+
+    fn parse_method<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        let lenient = self.config.lenient;
+        let base = self.position.offset();
+        let mut leniencies = Vec::new();
+
+        let mut start = input;
+        if lenient {
+            check_empty_lines(input, &self.config)?;
+
+            let (rest, empty_lines) = recognize(many0(eol))(input)
+                .map_err(|e| e.into_parse_error(input, ErrorKind::Method))?;
+            if !empty_lines.is_empty() {
+                leniencies.push(Leniency::new(LeniencyKind::LeadingEmptyLines, base));
+            }
+            start = rest;
+        }
+
+        check_start_line(start, 0, &self.config).map_err(|e| e.shift(input.offset(start)))?;
+
+        let (rest, (method, sep)) = tuple((method, separator(lenient)))(start)
+            .map_err(|e| e.into_parse_error(input, ErrorKind::Method))?;
+        leniencies.extend(leniency(input, sep, base));
+
+        let token = method;
+        let method = Method::from_bytes(token)
+            .map_err(|_| FromUtf8Err::init(token, ErrorKind::Method).at(input.offset(token)))?;
+
+        if !self.config.allows_method(&method) {
+            return Err(
+                FromUtf8Err::init(token, ErrorKind::MethodNotAllowed).at(input.offset(token))
+            );
+        }
+
+        self.method = Some(method);
+        self.head_start = base + input.offset(start);
+        self.leniencies.append(&mut leniencies);
+
+        Ok(rest)
+    }
+
+    fn parse_uri<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        check_start_line(input, self.head_offset(), &self.config)?;
+
+        let lenient = self.config.lenient;
+        let delimiters = if lenient { " \t\r\n" } else { " \r\n" };
+        let (rest, (uri, sep)) = tuple((is_not(delimiters), separator(lenient)))(input)
+            .map_err(|e| e.into_parse_error(input, ErrorKind::Uri))?;
+
+        let target = uri;
+        let uri = Uri::try_from(target)
+            .map_err(|_| FromUtf8Err::init(target, ErrorKind::Uri).at(input.offset(target)))?;
+
+        let form = TargetForm::of(&uri);
+        let mismatch = self
+            .method
+            .as_ref()
+            .is_some_and(|method| !form.allows(method));
+        if self.config.strict_target_form && mismatch {
+            return Err(FromUtf8Err::init(target, ErrorKind::TargetForm).at(input.offset(target)));
+        }
+
+        self.uri = Some(uri);
+        self.leniencies
+            .extend(leniency(input, sep, self.position.offset()));
+
+        Ok(rest)
+    }
+
+    fn parse_version<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        check_start_line(input, self.head_offset(), &self.config)?;
+
+        let (rest, (http_version, ending)) =
+            tuple((http_version, line_end(self.config.lenient)))(input)
+                .map_err(|e| e.into_parse_error(input, ErrorKind::Version))?;
+
+        let version = version_from_bytes(http_version, self.config.version_policy)
+            .map_err(|e| e.at(input.offset(http_version)))?;
+
+        self.version = Some(version);
+        self.leniencies
+            .extend(leniency(input, ending, self.position.offset()));
+
+        Ok(rest)
+    }
+
+    fn parse_headers<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        let parsed = self.field_progress.parsed();
+        let consumed = self.head_offset() + parsed;
+        check_fields(
+            &input[parsed..],
+            consumed,
+            self.field_progress.lines(),
+            &self.config,
+        )
+        .map_err(|e| e.shift(parsed))?;
+
+        let (rest, fields, leniencies) =
+            resume_field_lines(input, &mut self.field_progress, &self.config)?;
+        let headers = header_map(input, &fields, &self.config)?;
+
+        let base = self.position.offset();
+        self.leniencies
+            .extend(leniencies.into_iter().map(|l| l.shift(base)));
+
+        self.framing = FramingFields::collect(input, &fields, &self.position);
+
+        if let Some(raw) = self.raw.as_mut() {
+            let (lines, end) = raw_field_lines(input, &fields, rest);
+            raw.set_fields(lines, &headers, end);
+        }
+
+        self.headers = Some(headers);
+
+        Ok(rest)
+    }
 }
 
 pub struct Builder<'a, T> {
@@ -95,6 +326,15 @@ impl<'a, T> Builder<'a, T> {
             _phantom: PhantomData,
         }
     }
+
+    fn next<U>(input: &'a [u8], result: PartialRequest) -> Builder<'a, U> {
+        Builder {
+            input,
+            result,
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn build(mut self) -> PartialRequest {
         self.result.rest = self.input.to_vec();
         self.result
@@ -103,108 +343,114 @@ impl<'a, T> Builder<'a, T> {
 
 impl<'a> Builder<'a, NeedMethod> {
     pub fn method(mut self) -> Result<Builder<'a, NeedUri>, FromUtf8Err> {
-        let (rest, method) = terminated(method, tag(" "))(self.input)
-            .map_err(|e| e.into_parse_error(ErrorKind::Method))?;
-
-        self.result.method = Method::from_bytes(method).ok();
+        self.result
+            .step(&mut self.input, PartialRequest::parse_method)?;
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        Ok(Self::next(self.input, self.result))
     }
 }
 
 impl<'a> Builder<'a, NeedUri> {
     pub fn uri(mut self) -> Result<Builder<'a, NeedVersion>, FromUtf8Err> {
-        let (rest, uri) = terminated(is_not(" "), tag(" "))(self.input)
-            .map_err(|e| e.into_parse_error(ErrorKind::Uri))?;
-
-        let uri = String::from_utf8(uri.to_vec()).unwrap();
-        let uri = uri
-            .parse::<Uri>()
-            .map_err(|_| FromUtf8Err::init(uri, ErrorKind::Uri))?;
-
-        self.result.uri = Some(uri);
+        self.result
+            .step(&mut self.input, PartialRequest::parse_uri)?;
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        Ok(Self::next(self.input, self.result))
     }
 }
 
 impl<'a> Builder<'a, NeedVersion> {
     pub fn version(mut self) -> Result<Builder<'a, NeedHeader>, FromUtf8Err> {
-        let (rest, http_version) = terminated(http_version, tag("\r\n"))(self.input)
-            .map_err(|e| e.into_parse_error(ErrorKind::Version))?;
-
-        let version = match http_version {
-            b"HTTP/0.9" => Version::HTTP_09,
-            b"HTTP/1.0" => Version::HTTP_10,
-            b"HTTP/1.1" => Version::HTTP_11,
-            _ => unreachable!(),
-        };
-
-        self.result.version = Some(version);
+        self.result
+            .step(&mut self.input, PartialRequest::parse_version)?;
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        Ok(Self::next(self.input, self.result))
     }
 }
 
 impl<'a> Builder<'a, NeedHeader> {
     pub fn headers(mut self) -> Result<Builder<'a, NeedBody>, FromUtf8Err> {
-        let (rest, headers) = terminated(
-            separated_list0(
-                tag("\r\n"),
-                terminated(
-                    separated_pair(field_name, tuple((tag(":"), ows)), field_value),
-                    ows,
-                ),
-            ),
-            tag("\r\n\r\n"),
-        )(self.input)
-        .map_err(|e| e.into_parse_error(ErrorKind::Header))?;
+        self.result
+            .step(&mut self.input, PartialRequest::parse_headers)?;
 
-        let mut header_map = HeaderMap::new();
+        Ok(Self::next(self.input, self.result))
+    }
+}
 
-        for (key, value) in headers {
-            let name = HeaderName::from_bytes(key).map_err(|_| {
-                FromUtf8Err::init(String::from_utf8(key.to_vec()).unwrap(), ErrorKind::Header)
-            })?;
-            let val = HeaderValue::from_bytes(value).map_err(|_| {
-                FromUtf8Err::init(
-                    String::from_utf8(value.to_vec()).unwrap(),
-                    ErrorKind::Header,
-                )
-            })?;
+impl<'a> Builder<'a, NeedBody> {
+    pub fn rest(&self) -> &'a [u8] {
+        self.input
+    }
+
+    pub fn body_length(&self) -> Result<BodyLength, FromUtf8Err> {
+        self.result.body_length()
+    }
+
+    pub fn read_body(&self) -> Result<(Vec<u8>, &'a [u8]), FromUtf8Err> {
+        self.body_length()?
+            .read_body(self.input, &self.result.config)
+            .map_err(|e| e.locate(self.input, &self.result.position))
+    }
 
-            header_map.insert(name, val);
+    pub fn read_body_with_trailers(&self) -> Result<BodyWithTrailers<'a>, FromUtf8Err> {
+        let (body, trailers, rest) = self
+            .body_length()?
+            .read_body_with_trailers(self.input, &self.result.config)
+            .map_err(|e| e.locate(self.input, &self.result.position))?;
+
+        if let Some(headers) = self.result.headers.as_ref() {
+            validate_trailers(headers, &trailers).map_err(|e| {
+                let body = &self.input[..self.input.offset(rest)];
+                locate_trailer(e, body, &self.result.position)
+            })?;
         }
 
-        self.result.headers = Some(header_map);
+        Ok((body, trailers, rest))
+    }
+
+    pub fn framed(self) -> Result<(Request<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        let chunked = self.body_length()? == BodyLength::Chunked;
+        let (body, trailers, rest) = self.read_body_with_trailers()?;
+        let raw_body = (chunked && self.result.raw.is_some())
+            .then(|| RawBody::new(&self.input[..self.input.offset(rest)]));
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        let mut request = self.body(body);
+        if let Some(raw_body) = raw_body {
+            request.extensions_mut().insert(raw_body);
+        }
+        if !trailers.is_empty() {
+            request.extensions_mut().insert(Trailers::new(trailers));
+        }
+
+        Ok((request, rest))
     }
-}
 
-impl<'a> Builder<'a, NeedBody> {
     pub fn body<T>(self, body: T) -> Request<T> {
         unsafe {
+            let method = self.result.method.unwrap_unchecked();
+            let uri = self.result.uri.unwrap_unchecked();
+            let version = self.result.version.unwrap_unchecked();
+
+            let raw = self.result.raw.map(|mut raw| {
+                raw.set_canonical_start_line(
+                    request_line(&method, &uri, version).unwrap_or_default(),
+                );
+                raw
+            });
+
+            let form = TargetForm::of(&uri);
             let mut builder = Request::builder()
-                .method(self.result.method.unwrap_unchecked())
-                .uri(self.result.uri.unwrap_unchecked())
-                .version(self.result.version.unwrap_unchecked());
+                .method(method)
+                .uri(uri)
+                .version(version)
+                .extension(form);
+
+            if let Some(raw) = raw {
+                builder = builder.extension(raw);
+            }
+            if self.result.config.lenient {
+                builder = builder.extension(Leniencies::new(self.result.leniencies));
+            }
 
             for (name, value) in self.result.headers.unwrap_unchecked().iter() {
                 builder = builder.header(name.clone(), value);
@@ -214,3 +460,277 @@ impl<'a> Builder<'a, NeedBody> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::FromUtf8Framed;
+
+    use super::*;
+
+    #[test]
+    fn test_h2_preface() {
+        let err = PartialRequest::new()
+            .feed(b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::UnsupportedVersion);
+        assert_eq!(err.offset(), 6);
+        assert_eq!(err.kind().status_code().unwrap().as_u16(), 505);
+    }
+
+    #[test]
+    fn test_extension_method() {
+        let input = b"PROPFIND /file HTTP/1.1\r\nDepth: 1\r\n\r\n";
+
+        let req = PartialRequest::builder(input)
+            .method()
+            .unwrap()
+            .build()
+            .parse_rest(())
+            .unwrap();
+        assert_eq!(req.method().as_str(), "PROPFIND");
+
+        let config = ParserConfig {
+            allowed_methods: Some(vec![Method::GET, Method::HEAD]),
+            ..ParserConfig::default()
+        };
+        let err = PartialRequest::builder_with_config(input, config)
+            .method()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), &ErrorKind::MethodNotAllowed);
+    }
+
+    #[test]
+    fn test_feed() {
+        let input = b"GET /hello.htm HTTP/1.1\r\nHost: www.tutorialspoint.com\r\nConnection: Keep-Alive\r\n\r\nThisIsBody";
+        let head_len = input.len() - b"ThisIsBody".len();
+        let mut partial = PartialRequest::new();
+
+        for chunk in input[..head_len - 1].chunks(7) {
+            assert!(partial.feed(chunk).unwrap_err().is_incomplete());
+            assert!(!partial.is_complete());
+        }
+        partial.feed(&input[head_len - 1..]).unwrap();
+
+        assert!(partial.is_complete());
+        assert_eq!(partial.method(), &Some(Method::GET));
+        assert_eq!(partial.rest(), b"ThisIsBody");
+
+        let req = partial.parse_rest(()).unwrap();
+        assert_eq!(req.uri(), "/hello.htm");
+        assert_eq!(req.headers().get("Host").unwrap(), "www.tutorialspoint.com");
+    }
+
+    #[test]
+    fn test_feed_resumes_headers() {
+        let mut input = b"GET / HTTP/1.1\r\nX-Folded: a\r\n b\r\n".to_vec();
+        for i in 0..90 {
+            input.extend_from_slice(format!("X-{}: {}\r\n", i, "v".repeat(600)).as_bytes());
+        }
+        input.extend_from_slice(b"\r\nbody");
+
+        let config = ParserConfig {
+            obs_fold: crate::ObsFoldPolicy::Replace,
+            ..ParserConfig::default()
+        };
+        let mut partial = PartialRequest::with_config(config);
+        let (head, body) = input.split_at(input.len() - 4);
+        for chunk in head.chunks(8) {
+            if let Err(e) = partial.feed(chunk) {
+                assert!(e.is_incomplete());
+            }
+        }
+        partial.feed(body).unwrap();
+        assert_eq!(partial.rest(), b"body");
+
+        let headers = partial.headers().as_ref().unwrap();
+        assert_eq!(headers.len(), 91);
+        assert_eq!(headers.get("x-folded").unwrap(), "a b");
+
+        let config = ParserConfig {
+            max_header_count: 2,
+            ..ParserConfig::default()
+        };
+        let mut partial = PartialRequest::with_config(config);
+        assert!(partial
+            .feed(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\n")
+            .unwrap_err()
+            .is_incomplete());
+        let err = partial.feed(b"C: 3\r\n\r\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooManyHeaders);
+        assert_eq!(err.offset(), 28);
+    }
+
+    #[test]
+    fn test_framing_errors() {
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked, gzip\r\n\r\n";
+        let err = Request::<Vec<u8>>::from_utf8_framed(input).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TransferEncoding);
+        assert_eq!((err.offset(), err.line(), err.column()), (45, 3, 20));
+        assert!(err.to_string().contains("found \"chunked, gzip\""));
+
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!";
+        let mut partial = PartialRequest::new();
+        partial.feed(input).unwrap();
+        let err = partial.body_length().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::ContentLength);
+        assert_eq!((err.offset(), err.line(), err.column()), (52, 3, 17));
+
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 5, 5\r\n\r\nhello";
+        let (req, _) = Request::<Vec<u8>>::from_utf8_framed(input).unwrap();
+        assert_eq!(req.body(), b"hello");
+    }
+
+    #[test]
+    fn test_limits() {
+        let config = ParserConfig {
+            max_start_line_len: 32,
+            max_header_count: 2,
+            ..ParserConfig::default()
+        };
+
+        let mut partial = PartialRequest::with_config(config.clone());
+        assert!(partial.feed(b"GET /").unwrap_err().is_incomplete());
+        let err = partial.feed(&[b'a'; 64]).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::StartLineTooLong);
+        assert_eq!(err.offset(), 32);
+        assert_eq!(err.kind().status_code().unwrap().as_u16(), 414);
+
+        let mut partial = PartialRequest::with_config(config);
+        let err = partial
+            .feed(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::TooManyHeaders);
+        assert_eq!((err.line(), err.column()), (4, 1));
+
+        let config = ParserConfig {
+            max_head_size: 32,
+            ..ParserConfig::default()
+        };
+        let mut input = b"GET /".to_vec();
+        input.extend_from_slice(&[b'a'; 200]);
+        input.extend_from_slice(b" HTTP/1.1\r\n\r\n");
+
+        let err = PartialRequest::builder_with_config(&input, config)
+            .method()
+            .and_then(|builder| builder.uri())
+            .and_then(|builder| builder.version())
+            .and_then(|builder| builder.headers())
+            .map(|builder| builder.body(()))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeadTooLarge);
+        assert_eq!(err.offset(), 32);
+    }
+
+    #[test]
+    fn test_target_form() {
+        let cases: [(&[u8], TargetForm); 4] = [
+            (b"GET /index.html?q=1 HTTP/1.1\r\n\r\n", TargetForm::Origin),
+            (
+                b"GET http://example.com/ HTTP/1.1\r\n\r\n",
+                TargetForm::Absolute,
+            ),
+            (
+                b"CONNECT example.com:443 HTTP/1.1\r\n\r\n",
+                TargetForm::Authority,
+            ),
+            (b"OPTIONS * HTTP/1.1\r\n\r\n", TargetForm::Asterisk),
+        ];
+        let strict = ParserConfig {
+            strict_target_form: true,
+            ..ParserConfig::default()
+        };
+
+        for (input, form) in cases {
+            let mut partial = PartialRequest::with_config(strict.clone());
+            partial.feed(input).unwrap();
+            assert_eq!(partial.target_form(), Some(form));
+
+            let req = partial.parse_rest(()).unwrap();
+            assert_eq!(req.extensions().get::<TargetForm>(), Some(&form));
+        }
+
+        for input in [
+            &b"GET example.com:443 HTTP/1.1\r\n\r\n"[..],
+            b"CONNECT /index.html HTTP/1.1\r\n\r\n",
+            b"GET * HTTP/1.1\r\n\r\n",
+        ] {
+            assert!(PartialRequest::new().feed(input).is_ok());
+
+            let err = PartialRequest::with_config(strict.clone())
+                .feed(input)
+                .unwrap_err();
+            assert_eq!(err.kind(), &ErrorKind::TargetForm);
+            assert_eq!(
+                err.column(),
+                input.iter().position(|&c| c == b' ').unwrap() + 2
+            );
+        }
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = b"\r\n\nGET  /a\tHTTP/1.1\nHost : example.com\r\n\nbody";
+        let config = ParserConfig {
+            lenient: true,
+            ..ParserConfig::default()
+        };
+
+        assert!(PartialRequest::new().feed(input).is_err());
+
+        let mut partial = PartialRequest::with_config(config);
+        for chunk in input.chunks(3) {
+            let _ = partial.feed(chunk);
+        }
+        assert!(partial.is_complete());
+        assert_eq!(partial.rest(), b"body");
+
+        let leniencies: Vec<_> = partial
+            .leniencies()
+            .iter()
+            .map(|l| (l.kind(), l.offset()))
+            .collect();
+        assert_eq!(
+            leniencies,
+            [
+                (LeniencyKind::LeadingEmptyLines, 0),
+                (LeniencyKind::ExtraWhitespace, 6),
+                (LeniencyKind::ExtraWhitespace, 10),
+                (LeniencyKind::BareLf, 19),
+                (LeniencyKind::FieldNameWhitespace, 24),
+                (LeniencyKind::BareLf, 40),
+            ]
+        );
+
+        let req = partial.parse_rest(()).unwrap();
+        assert_eq!(req.uri(), "/a");
+        assert_eq!(req.headers().get("host").unwrap(), "example.com");
+        let leniencies = req.extensions().get::<Leniencies>().unwrap();
+        assert_eq!(leniencies.as_slice().len(), 6);
+        let config = ParserConfig {
+            lenient: true,
+            max_start_line_len: 64,
+            max_head_size: 128,
+            ..ParserConfig::default()
+        };
+        let mut partial = PartialRequest::with_config(config);
+        for _ in 0..32 {
+            assert!(partial.feed(b"\r\n").unwrap_err().is_incomplete());
+        }
+        let err = partial.feed(b"\r\n").unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::HeadTooLarge);
+        assert_eq!(err.offset(), 64);
+
+        let config = ParserConfig {
+            lenient: true,
+            max_start_line_len: 20,
+            ..ParserConfig::default()
+        };
+        let mut partial = PartialRequest::with_config(config);
+        let err = partial
+            .feed(b"\r\n\r\nGET /aaaaaaaaaaaaaaaaaaaa HTTP/1.1\r\n")
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::StartLineTooLong);
+        assert_eq!(err.offset(), 24);
+    }
+}
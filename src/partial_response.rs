@@ -1,59 +1,313 @@
-use http::header::HeaderName;
-use http::{HeaderMap, HeaderValue, Response, StatusCode, Version};
+use http::{HeaderMap, Method, Response, StatusCode, Version};
 
-use nom::{bytes::complete::*, multi::*, sequence::*};
+use nom::{sequence::*, Needed, Offset};
 
 use std::marker::PhantomData;
 
+use crate::body::*;
+use crate::chunked::BodyWithTrailers;
+use crate::config::*;
 use crate::error::*;
+use crate::extensions::{
+    InterimResponses, Leniencies, Leniency, RawBody, RawHead, ReasonPhrase, Trailers,
+};
+use crate::fields::{header_map, raw_field_lines, resume_field_lines, FieldProgress};
 use crate::http_combinator::*;
-use crate::FromUtf8;
+use crate::http_elements::{status_line, version_from_bytes};
+use crate::lenient::{leniency, line_end, separator};
+use crate::limits::{check_fields, check_start_line};
 
 pub struct NeedVersion;
 pub struct NeedStatus;
 pub struct NeedHeader;
 pub struct NeedBody;
 
+#[derive(Debug, Clone, Default)]
 pub struct PartialResponse {
     version: Option<Version>,
     status: Option<StatusCode>,
+    reason: Option<ReasonPhrase>,
     headers: Option<HeaderMap>,
     rest: Vec<u8>,
+    position: Position,
+    head_start: usize,
+    config: ParserConfig,
+    raw: Option<RawHead>,
+    leniencies: Vec<Leniency>,
+    field_progress: FieldProgress,
+    framing: FramingFields,
 }
 
 impl PartialResponse {
-    pub fn builder(input: &[u8]) -> Builder<NeedVersion> {
-        let result = PartialResponse {
-            version: None,
-            status: None,
-            headers: None,
-            rest: Vec::new(),
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self {
+            raw: config.preserve_raw.then(RawHead::default),
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn builder(input: &[u8]) -> Builder<'_, NeedVersion> {
+        Builder::init(input, PartialResponse::new())
+    }
+
+    pub fn builder_with_config(input: &[u8], config: ParserConfig) -> Builder<'_, NeedVersion> {
+        Builder::init(input, PartialResponse::with_config(config))
+    }
+
+    pub(crate) fn builder_at(
+        input: &[u8],
+        config: ParserConfig,
+        position: Position,
+    ) -> Builder<'_, NeedVersion> {
+        let partial = PartialResponse {
+            head_start: position.offset(),
+            position,
+            ..PartialResponse::with_config(config)
         };
-        Builder::init(input, result)
+
+        Builder::init(input, partial)
+    }
+
+    pub fn framed_with_interim<'a>(
+        input: &'a [u8],
+        method: &Method,
+        config: ParserConfig,
+    ) -> Result<(Response<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        let mut interim = Vec::new();
+        let mut rest = input;
+        let mut position = Position::default();
+
+        loop {
+            let (mut response, after) =
+                PartialResponse::builder_at(rest, config.clone(), position.clone())
+                    .version()?
+                    .status()?
+                    .headers()?
+                    .framed_for(method)?;
+            position.advance(&rest[..rest.offset(after)]);
+            rest = after;
+
+            let status = response.status();
+            if status.is_informational() && status != StatusCode::SWITCHING_PROTOCOLS {
+                interim.push(response.map(|_| ()));
+                continue;
+            }
+
+            if !interim.is_empty() {
+                response
+                    .extensions_mut()
+                    .insert(InterimResponses::new(interim));
+            }
+
+            return Ok((response, rest));
+        }
+    }
+
+    pub fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        let mut buf = std::mem::take(&mut self.rest);
+        buf.extend_from_slice(input);
+
+        let mut rest = &buf[..];
+        let result = self.advance(&mut rest);
+        let consumed = buf.len() - rest.len();
+
+        buf.drain(..consumed);
+        self.rest = buf;
+
+        result
     }
 
     pub fn parse_rest<T>(mut self, body: T) -> Result<Response<T>, FromUtf8Err> {
-        let mut buf = Vec::new();
-        std::mem::swap(&mut buf, &mut self.rest);
-
-        let result = if self.headers.is_some() {
-            Builder::<NeedBody>::init(&buf, self)
-        } else {
-            if self.status.is_some() {
-                Builder::<NeedHeader>::init(&buf, self)
+        let buf = std::mem::take(&mut self.rest);
+
+        let mut rest = &buf[..];
+        self.advance(&mut rest)?;
+
+        Ok(Builder::<NeedBody>::init(rest, self).body(body))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub(crate) fn framed_for<'a>(
+        mut self,
+        input: &'a [u8],
+        method: &Method,
+    ) -> Result<(Response<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        self.rest.clear();
+
+        Builder::<NeedBody>::init(input, self).framed_for(method)
+    }
+
+    fn advance(&mut self, input: &mut &[u8]) -> Result<(), FromUtf8Err> {
+        if self.version.is_none() {
+            self.step(input, Self::parse_version)?;
+        }
+        if self.status.is_none() {
+            self.step(input, Self::parse_status)?;
+        }
+        if self.headers.is_none() {
+            self.step(input, Self::parse_headers)?;
+        }
+
+        Ok(())
+    }
+
+    fn head_offset(&self) -> usize {
+        self.position.offset() - self.head_start
+    }
+
+    fn step<'a>(
+        &mut self,
+        input: &mut &'a [u8],
+        parse: fn(&mut Self, &'a [u8]) -> Result<&'a [u8], FromUtf8Err>,
+    ) -> Result<(), FromUtf8Err> {
+        let rest = parse(self, input).map_err(|e| {
+            if e.is_incomplete() {
+                e
             } else {
-                if self.version.is_some() {
-                    Builder::<NeedStatus>::init(&buf, self)
-                } else {
-                    Builder::<NeedVersion>::init(&buf, self).version()?
-                }
-                .status()?
+                e.locate(input, &self.position)
+            }
+        })?;
+        let consumed = &input[..input.len() - rest.len()];
+
+        if self.headers.is_none() {
+            if let Some(raw) = self.raw.as_mut() {
+                raw.push_start_line(consumed);
             }
-            .headers()?
         }
-        .body(body);
+        self.position.advance(consumed);
+        *input = rest;
+
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.headers.is_some()
+    }
+
+    pub fn version(&self) -> &Option<Version> {
+        &self.version
+    }
+
+    pub fn status(&self) -> &Option<StatusCode> {
+        &self.status
+    }
+
+    pub fn reason(&self) -> &Option<ReasonPhrase> {
+        &self.reason
+    }
+
+    pub fn headers(&self) -> &Option<HeaderMap> {
+        &self.headers
+    }
+
+    pub fn rest(&self) -> &[u8] {
+        &self.rest
+    }
+
+    pub fn leniencies(&self) -> &[Leniency] {
+        &self.leniencies
+    }
+
+    pub fn raw(&self) -> &Option<RawHead> {
+        &self.raw
+    }
+
+    pub fn body_length(&self) -> Result<BodyLength, FromUtf8Err> {
+        let headers = self
+            .headers
+            .as_ref()
+            .ok_or_else(|| FromUtf8Err::init(Vec::new(), ErrorKind::Incomplete(Needed::Unknown)))?;
+
+        let status = self.status.unwrap_or_default();
+
+        response_body_length(status, headers).map_err(|e| self.framing.locate(e))
+    }
+
+    pub fn body_length_for(&self, method: &Method) -> Result<BodyLength, FromUtf8Err> {
+        let headers = self
+            .headers
+            .as_ref()
+            .ok_or_else(|| FromUtf8Err::init(Vec::new(), ErrorKind::Incomplete(Needed::Unknown)))?;
+        let status = self.status.unwrap_or_default();
+
+        response_body_length_for(method, status, headers).map_err(|e| self.framing.locate(e))
+    }
+
+    fn parse_version<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        check_start_line(input, self.head_offset(), &self.config)?;
+
+        let (rest, (http_version, sep)) =
+            tuple((http_version, separator(self.config.lenient)))(input)
+                .map_err(|e| e.into_parse_error(input, ErrorKind::Version))?;
+
+        let version = version_from_bytes(http_version, self.config.version_policy)
+            .map_err(|e| e.at(input.offset(http_version)))?;
+
+        self.version = Some(version);
+        self.leniencies
+            .extend(leniency(input, sep, self.position.offset()));
+
+        Ok(rest)
+    }
+
+    fn parse_status<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        check_start_line(input, self.head_offset(), &self.config)?;
+
+        let lenient = self.config.lenient;
+        let (rest, ((status_code, sep, reason_phrase), ending)) = tuple((
+            tuple((status_code, separator(lenient), reason_phrase)),
+            line_end(lenient),
+        ))(input)
+        .map_err(|e| e.into_parse_error(input, ErrorKind::StatusCode))?;
+
+        let status = StatusCode::from_bytes(status_code).map_err(|_| {
+            FromUtf8Err::init(status_code, ErrorKind::StatusCode).at(input.offset(status_code))
+        })?;
+
+        self.status = Some(status);
+        self.reason = Some(ReasonPhrase::new(status, reason_phrase));
+
+        let base = self.position.offset();
+        self.leniencies.extend(leniency(input, sep, base));
+        self.leniencies.extend(leniency(input, ending, base));
+
+        Ok(rest)
+    }
+
+    fn parse_headers<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8], FromUtf8Err> {
+        let parsed = self.field_progress.parsed();
+        let consumed = self.head_offset() + parsed;
+        check_fields(
+            &input[parsed..],
+            consumed,
+            self.field_progress.lines(),
+            &self.config,
+        )
+        .map_err(|e| e.shift(parsed))?;
+
+        let (rest, fields, leniencies) =
+            resume_field_lines(input, &mut self.field_progress, &self.config)?;
+        let headers = header_map(input, &fields, &self.config)?;
+
+        let base = self.position.offset();
+        self.leniencies
+            .extend(leniencies.into_iter().map(|l| l.shift(base)));
+
+        self.framing = FramingFields::collect(input, &fields, &self.position);
+
+        if let Some(raw) = self.raw.as_mut() {
+            let (lines, end) = raw_field_lines(input, &fields, rest);
+            raw.set_fields(lines, &headers, end);
+        }
 
-        Ok(result)
+        self.headers = Some(headers);
+
+        Ok(rest)
     }
 }
 
@@ -71,6 +325,15 @@ impl<'a, T> Builder<'a, T> {
             _phantom: PhantomData,
         }
     }
+
+    fn next<U>(input: &'a [u8], result: PartialResponse) -> Builder<'a, U> {
+        Builder {
+            input,
+            result,
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn build(mut self) -> PartialResponse {
         self.result.rest = self.input.to_vec();
         self.result
@@ -79,95 +342,118 @@ impl<'a, T> Builder<'a, T> {
 
 impl<'a> Builder<'a, NeedVersion> {
     pub fn version(mut self) -> Result<Builder<'a, NeedStatus>, FromUtf8Err> {
-        let (rest, http_version) = terminated(http_version, tag(" "))(self.input)
-            .map_err(|e| e.into_parse_error(ErrorKind::Version))?;
-
-        let version = match http_version {
-            b"HTTP/0.9" => Version::HTTP_09,
-            b"HTTP/1.0" => Version::HTTP_10,
-            b"HTTP/1.1" => Version::HTTP_11,
-            _ => unreachable!(),
-        };
-
-        self.result.version = Some(version);
+        self.result
+            .step(&mut self.input, PartialResponse::parse_version)?;
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        Ok(Self::next(self.input, self.result))
     }
 }
 
 impl<'a> Builder<'a, NeedStatus> {
     pub fn status(mut self) -> Result<Builder<'a, NeedHeader>, FromUtf8Err> {
-        let (rest, (status_code, _sp, _reason_phrase)) =
-            terminated(tuple((status_code, tag(" "), reason_phrase)), tag("\r\n"))(self.input)
-                .map_err(|e| e.into_parse_error(ErrorKind::Version))?;
-
-        let status = StatusCode::from_bytes(status_code).map_err(|_| {
-            FromUtf8Err::init(
-                String::from_utf8(status_code.to_vec()).unwrap(),
-                ErrorKind::StatusCode,
-            )
-        })?;
-
-        self.result.status = Some(status);
+        self.result
+            .step(&mut self.input, PartialResponse::parse_status)?;
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+        Ok(Self::next(self.input, self.result))
     }
 }
 
 impl<'a> Builder<'a, NeedHeader> {
     pub fn headers(mut self) -> Result<Builder<'a, NeedBody>, FromUtf8Err> {
-        let (rest, headers) = terminated(
-            separated_list0(
-                tag("\r\n"),
-                terminated(
-                    separated_pair(field_name, tuple((tag(":"), ows)), field_value),
-                    ows,
-                ),
-            ),
-            tag("\r\n\r\n"),
-        )(self.input)
-        .map_err(|e| e.into_parse_error(ErrorKind::Header))?;
-
-        let mut header_map = HeaderMap::new();
-
-        for (key, value) in headers {
-            let name = HeaderName::from_bytes(key).map_err(|_| {
-                FromUtf8Err::init(String::from_utf8(key.to_vec()).unwrap(), ErrorKind::Header)
-            })?;
-            let val = HeaderValue::from_bytes(value).map_err(|_| {
-                FromUtf8Err::init(
-                    String::from_utf8(value.to_vec()).unwrap(),
-                    ErrorKind::Header,
-                )
-            })?;
+        self.result
+            .step(&mut self.input, PartialResponse::parse_headers)?;
+
+        Ok(Self::next(self.input, self.result))
+    }
+}
 
-            header_map.insert(name, val);
+impl<'a> Builder<'a, NeedBody> {
+    pub fn rest(&self) -> &'a [u8] {
+        self.input
+    }
+
+    pub fn body_length(&self) -> Result<BodyLength, FromUtf8Err> {
+        self.result.body_length()
+    }
+
+    pub fn read_body(&self) -> Result<(Vec<u8>, &'a [u8]), FromUtf8Err> {
+        self.body_length()?
+            .read_body(self.input, &self.result.config)
+            .map_err(|e| e.locate(self.input, &self.result.position))
+    }
+
+    pub fn body_length_for(&self, method: &Method) -> Result<BodyLength, FromUtf8Err> {
+        self.result.body_length_for(method)
+    }
+
+    pub fn read_body_with_trailers(&self) -> Result<BodyWithTrailers<'a>, FromUtf8Err> {
+        self.read_with_trailers(self.body_length()?)
+    }
+
+    fn read_with_trailers(&self, length: BodyLength) -> Result<BodyWithTrailers<'a>, FromUtf8Err> {
+        let (body, trailers, rest) = length
+            .read_body_with_trailers(self.input, &self.result.config)
+            .map_err(|e| e.locate(self.input, &self.result.position))?;
+
+        if let Some(headers) = self.result.headers.as_ref() {
+            validate_trailers(headers, &trailers).map_err(|e| {
+                let body = &self.input[..self.input.offset(rest)];
+                locate_trailer(e, body, &self.result.position)
+            })?;
         }
 
-        self.result.headers = Some(header_map);
+        Ok((body, trailers, rest))
+    }
 
-        Ok(Builder {
-            input: rest,
-            result: self.result,
-            _phantom: PhantomData,
-        })
+    pub fn framed(self) -> Result<(Response<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        let length = self.body_length()?;
+        self.frame(length)
+    }
+
+    pub fn framed_for(self, method: &Method) -> Result<(Response<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        let length = self.body_length_for(method)?;
+        self.frame(length)
+    }
+
+    fn frame(self, length: BodyLength) -> Result<(Response<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        let chunked = length == BodyLength::Chunked;
+        let (body, trailers, rest) = self.read_with_trailers(length)?;
+        let raw_body = (chunked && self.result.raw.is_some())
+            .then(|| RawBody::new(&self.input[..self.input.offset(rest)]));
+
+        let mut response = self.body(body);
+        if let Some(raw_body) = raw_body {
+            response.extensions_mut().insert(raw_body);
+        }
+        if !trailers.is_empty() {
+            response.extensions_mut().insert(Trailers::new(trailers));
+        }
+
+        Ok((response, rest))
     }
-}
 
-impl<'a> Builder<'a, NeedBody> {
     pub fn body<T>(self, body: T) -> Response<T> {
         unsafe {
-            let mut builder = Response::builder()
-                .version(self.result.version.unwrap_unchecked())
-                .status(self.result.status.unwrap_unchecked());
+            let version = self.result.version.unwrap_unchecked();
+            let status = self.result.status.unwrap_unchecked();
+
+            let raw = self.result.raw.map(|mut raw| {
+                let start_line = status_line(version, status, self.result.reason.as_ref());
+                raw.set_canonical_start_line(start_line.unwrap_or_default());
+                raw
+            });
+
+            let mut builder = Response::builder().version(version).status(status);
+
+            if let Some(reason) = self.result.reason {
+                builder = builder.extension(reason);
+            }
+            if let Some(raw) = raw {
+                builder = builder.extension(raw);
+            }
+            if self.result.config.lenient {
+                builder = builder.extension(Leniencies::new(self.result.leniencies));
+            }
 
             for (name, value) in self.result.headers.unwrap_unchecked().iter() {
                 builder = builder.header(name.clone(), value);
@@ -177,3 +463,161 @@ impl<'a> Builder<'a, NeedBody> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_feed() {
+        let mut partial = PartialResponse::new();
+
+        let err = partial.feed(b"HTTP/1.1 200 OK\r\nContent-Le").unwrap_err();
+        assert!(err.is_incomplete());
+        assert_eq!(partial.status(), &Some(StatusCode::OK));
+        assert_eq!(partial.rest(), b"Content-Le");
+
+        partial.feed(b"ngth: 2\r\n\r\nok").unwrap();
+        assert!(partial.is_complete());
+        assert_eq!(partial.rest(), b"ok");
+
+        let res = partial.parse_rest(()).unwrap();
+        assert_eq!(res.headers().get("content-length").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let err = PartialResponse::builder(b"HTTP/1.2 200 OK\r\n\r\n")
+            .version()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), &ErrorKind::UnsupportedVersion);
+
+        let config = ParserConfig {
+            version_policy: VersionPolicy::DowngradeMinor,
+            ..ParserConfig::default()
+        };
+        let mut partial = PartialResponse::with_config(config);
+        partial.feed(b"HTTP/1.2 200 OK\r\n\r\n").unwrap();
+        assert_eq!(partial.version(), &Some(Version::HTTP_11));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input = b"HTTP/1.1 \t200  OK\nContent-Length: 2\n\nok";
+        let config = ParserConfig {
+            lenient: true,
+            ..ParserConfig::default()
+        };
+
+        let (res, rest) = PartialResponse::builder_with_config(input, config)
+            .version()
+            .unwrap()
+            .status()
+            .unwrap()
+            .headers()
+            .unwrap()
+            .framed()
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), b"ok");
+
+        let leniencies = res.extensions().get::<Leniencies>().unwrap();
+        let kinds: Vec<_> = leniencies.iter().map(|l| (l.kind(), l.offset())).collect();
+        assert_eq!(
+            kinds,
+            [
+                (crate::LeniencyKind::ExtraWhitespace, 8),
+                (crate::LeniencyKind::ExtraWhitespace, 13),
+                (crate::LeniencyKind::BareLf, 17),
+                (crate::LeniencyKind::BareLf, 35),
+                (crate::LeniencyKind::BareLf, 36),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_framed_for() {
+        let input = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHTTP/1.1 304 Not Modified\r\nContent-Length: 5\r\n\r\nHTTP/1.1 200 OK\r\n\r\n";
+
+        let (res, rest) = PartialResponse::builder(input)
+            .version()
+            .unwrap()
+            .status()
+            .unwrap()
+            .headers()
+            .unwrap()
+            .framed_for(&Method::HEAD)
+            .unwrap();
+        assert!(res.body().is_empty());
+
+        let builder = PartialResponse::builder(rest)
+            .version()
+            .unwrap()
+            .status()
+            .unwrap()
+            .headers()
+            .unwrap();
+        assert_eq!(builder.body_length().unwrap(), BodyLength::NoBody);
+        let (res, rest) = builder.framed_for(&Method::GET).unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(rest, b"HTTP/1.1 200 OK\r\n\r\n");
+    }
+
+    #[test]
+    fn test_interim_responses() {
+        let input = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload; as=style\r\nLink: </script.js>; rel=preload; as=script\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1";
+
+        let (res, rest) =
+            PartialResponse::framed_with_interim(input, &Method::GET, ParserConfig::default())
+                .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), b"ok");
+        assert_eq!(rest, b"HTTP/1.1");
+
+        let interim = res.extensions().get::<InterimResponses>().unwrap();
+        let statuses: Vec<_> = interim
+            .responses()
+            .iter()
+            .map(|r| r.status().as_u16())
+            .collect();
+        assert_eq!(statuses, [100, 103]);
+
+        let links: Vec<_> = interim.early_hints().collect();
+        assert_eq!(
+            links,
+            [
+                "</style.css>; rel=preload; as=style",
+                "</script.js>; rel=preload; as=script"
+            ]
+        );
+
+        let err = PartialResponse::framed_with_interim(
+            &input[..60],
+            &Method::GET,
+            ParserConfig::default(),
+        )
+        .unwrap_err();
+        assert!(err.is_incomplete());
+
+        let err = PartialResponse::framed_with_interim(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nBad Header\r\n\r\n",
+            &Method::GET,
+            ParserConfig::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Header);
+        assert_eq!((err.offset(), err.line(), err.column()), (45, 4, 4));
+
+        let (res, rest) = PartialResponse::framed_with_interim(
+            b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\r\n\x81\x00",
+            &Method::GET,
+            ParserConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert!(res.extensions().get::<InterimResponses>().is_none());
+        assert_eq!(rest, b"\x81\x00");
+    }
+}
@@ -0,0 +1,157 @@
+use http::Request;
+
+use std::ops::Range;
+
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::PartialRequest;
+
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Pipelined {
+    Message(Request<Vec<u8>>, Range<usize>),
+    Incomplete(Range<usize>),
+}
+
+pub struct Pipeline<'a> {
+    input: &'a [u8],
+    offset: usize,
+    position: Position,
+    config: ParserConfig,
+    done: bool,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_config(input, ParserConfig::default())
+    }
+
+    pub fn with_config(input: &'a [u8], config: ParserConfig) -> Self {
+        Self {
+            input,
+            offset: 0,
+            position: Position::default(),
+            config,
+            done: false,
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn rest(&self) -> &'a [u8] {
+        &self.input[self.offset..]
+    }
+
+    fn parse_next(&self, input: &'a [u8]) -> Result<(Request<Vec<u8>>, &'a [u8]), FromUtf8Err> {
+        PartialRequest::builder_at(input, self.config.clone(), self.position.clone())
+            .method()?
+            .uri()?
+            .version()?
+            .headers()?
+            .framed()
+    }
+}
+
+impl Iterator for Pipeline<'_> {
+    type Item = Result<Pipelined, FromUtf8Err>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.rest();
+        if self.done || input.is_empty() {
+            return None;
+        }
+
+        match self.parse_next(input) {
+            Ok((request, rest)) => {
+                let start = self.offset;
+                self.offset += input.len() - rest.len();
+                self.position.advance(&self.input[start..self.offset]);
+
+                Some(Ok(Pipelined::Message(request, start..self.offset)))
+            }
+            Err(e) if e.is_incomplete() => {
+                self.done = true;
+
+                Some(Ok(Pipelined::Incomplete(self.offset..self.input.len())))
+            }
+            Err(e) => {
+                self.done = true;
+
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let input = b"GET /a HTTP/1.1\r\nHost: a\r\n\r\nPOST /b HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcPUT /c HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\nGET /d HTTP/1.1\r\nHo";
+
+        let mut pipeline = Pipeline::new(input);
+        let mut ranges = Vec::new();
+        let mut methods = Vec::new();
+        for item in pipeline.by_ref() {
+            match item.unwrap() {
+                Pipelined::Message(request, range) => {
+                    methods.push(request.method().clone());
+                    ranges.push(range);
+                }
+                Pipelined::Incomplete(range) => {
+                    assert_eq!(&input[range], b"GET /d HTTP/1.1\r\nHo");
+                }
+            }
+        }
+
+        assert_eq!(methods, [Method::GET, Method::POST, Method::PUT]);
+        assert_eq!(ranges, [0..28, 28..70, 70..129]);
+        assert_eq!(&input[ranges[1].clone()][..4], b"POST");
+        assert_eq!(pipeline.offset(), 129);
+        assert_eq!(pipeline.rest(), b"GET /d HTTP/1.1\r\nHo");
+    }
+
+    #[test]
+    fn test_pipeline_error() {
+        let input = b"GET / HTTP/1.1\r\n\r\nBAD REQUEST\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+
+        let items: Vec<_> = Pipeline::new(input).collect();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], Ok(Pipelined::Message(_, _))));
+        let err = items[1].as_ref().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Uri);
+        assert_eq!((err.offset(), err.line(), err.column()), (29, 3, 12));
+
+        assert_eq!(Pipeline::new(b"").count(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_lenient() {
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 1\r\n\r\na\r\nGET / HTTP/1.1\r\n\r\n";
+        let config = ParserConfig {
+            lenient: true,
+            max_start_line_len: 20,
+            max_head_size: 40,
+            ..ParserConfig::default()
+        };
+
+        let mut methods = Vec::new();
+        for item in Pipeline::with_config(input, config) {
+            match item.unwrap() {
+                Pipelined::Message(request, _) => methods.push(request.method().clone()),
+                Pipelined::Incomplete(range) => panic!("incomplete at {range:?}"),
+            }
+        }
+        assert_eq!(methods, [Method::POST, Method::GET]);
+
+        let err = Pipeline::new(input).nth(1).unwrap().unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Method);
+        assert_eq!(err.offset(), 39);
+    }
+}
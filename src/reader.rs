@@ -0,0 +1,394 @@
+use http::{HeaderMap, Method, Request, Response};
+
+use nom::{character::streaming::crlf, sequence::*};
+
+use std::io::{self, BufRead, Read};
+
+use crate::body::{validate_trailers, BodyLength};
+use crate::config::ParserConfig;
+use crate::error::*;
+use crate::fields::{header_map, parse_field_lines};
+use crate::http_combinator::*;
+use crate::limits::check_fields;
+use crate::{PartialRequest, PartialResponse};
+
+trait Partial {
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err>;
+    fn rest(&self) -> &[u8];
+}
+
+impl Partial for PartialRequest {
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        PartialRequest::feed(self, input)
+    }
+
+    fn rest(&self) -> &[u8] {
+        PartialRequest::rest(self)
+    }
+}
+
+impl Partial for PartialResponse {
+    fn feed(&mut self, input: &[u8]) -> Result<(), FromUtf8Err> {
+        PartialResponse::feed(self, input)
+    }
+
+    fn rest(&self) -> &[u8] {
+        PartialResponse::rest(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chunk {
+    Size,
+    Data(usize),
+    DataEnd,
+}
+
+#[derive(Debug)]
+enum BodyState {
+    Done,
+    Fixed(usize),
+    Chunked(Chunk, HeaderMap),
+    UntilClose,
+}
+
+fn invalid_data(e: FromUtf8Err) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+pub struct MessageReader<R> {
+    inner: R,
+    config: ParserConfig,
+    pending: Vec<u8>,
+    body: BodyState,
+    trailers: Option<HeaderMap>,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_config(inner, ParserConfig::default())
+    }
+
+    pub fn with_config(inner: R, config: ParserConfig) -> Self {
+        Self {
+            inner,
+            config,
+            pending: Vec::new(),
+            body: BodyState::Done,
+            trailers: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
+    }
+
+    pub fn read_request(&mut self) -> io::Result<Option<Request<BodyReader<'_, R>>>> {
+        let mut partial = PartialRequest::with_config(self.config.clone());
+        if !self.read_head(&mut partial)? {
+            return Ok(None);
+        }
+
+        let length = partial.body_length().map_err(invalid_data)?;
+        let headers = partial.headers().clone().unwrap_or_default();
+        self.start_body(length, headers);
+
+        partial
+            .parse_rest(BodyReader { reader: self })
+            .map(Some)
+            .map_err(invalid_data)
+    }
+
+    pub fn read_response(
+        &mut self,
+        method: &Method,
+    ) -> io::Result<Option<Response<BodyReader<'_, R>>>> {
+        let mut partial = PartialResponse::with_config(self.config.clone());
+        if !self.read_head(&mut partial)? {
+            return Ok(None);
+        }
+
+        let length = partial.body_length_for(method).map_err(invalid_data)?;
+        let headers = partial.headers().clone().unwrap_or_default();
+        self.start_body(length, headers);
+
+        partial
+            .parse_rest(BodyReader { reader: self })
+            .map(Some)
+            .map_err(invalid_data)
+    }
+
+    fn read_head(&mut self, partial: &mut impl Partial) -> io::Result<bool> {
+        io::copy(&mut BodyReader { reader: self }, &mut io::sink())?;
+
+        let mut read_any = !self.pending.is_empty();
+        let mut result = partial.feed(&std::mem::take(&mut self.pending));
+
+        while result.as_ref().is_err_and(|e| e.is_incomplete()) {
+            let buf = self.inner.fill_buf()?;
+            if buf.is_empty() {
+                if read_any {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                return Ok(false);
+            }
+
+            let len = buf.len();
+            read_any = true;
+            result = partial.feed(buf);
+            self.inner.consume(len);
+        }
+
+        result.map_err(invalid_data)?;
+        self.pending = partial.rest().to_vec();
+
+        Ok(true)
+    }
+
+    fn start_body(&mut self, length: BodyLength, headers: HeaderMap) {
+        self.trailers = None;
+        self.body = match length {
+            BodyLength::NoBody | BodyLength::Fixed(0) => BodyState::Done,
+            BodyLength::Fixed(len) => BodyState::Fixed(len),
+            BodyLength::Chunked => BodyState::Chunked(Chunk::Size, headers),
+            BodyLength::UntilClose => BodyState::UntilClose,
+        };
+    }
+
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pending.is_empty() {
+            self.inner.fill_buf()
+        } else {
+            Ok(&self.pending)
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.pending.is_empty() {
+            self.inner.consume(amt);
+        } else {
+            self.pending.drain(..amt);
+        }
+    }
+
+    fn copy_to(&mut self, out: &mut [u8], limit: usize) -> io::Result<usize> {
+        let buf = self.fill_buf()?;
+        let len = buf.len().min(out.len()).min(limit);
+        out[..len].copy_from_slice(&buf[..len]);
+        self.consume(len);
+
+        Ok(len)
+    }
+
+    fn read_line(&mut self, line: &mut Vec<u8>, limit: usize, kind: ErrorKind) -> io::Result<()> {
+        loop {
+            let buf = self.fill_buf()?;
+            if buf.is_empty() {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            let (len, done) = match buf.iter().position(|&c| c == b'\n') {
+                Some(at) => (at + 1, true),
+                None => (buf.len(), false),
+            };
+            line.extend_from_slice(&buf[..len]);
+            self.consume(len);
+
+            if line.len() > limit {
+                return Err(invalid_data(FromUtf8Err::init(line.as_slice(), kind)));
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_chunk_size(&mut self) -> io::Result<usize> {
+        let mut line = Vec::new();
+        self.read_line(&mut line, self.config.max_start_line_len, ErrorKind::Chunk)?;
+
+        let (_, (size, _ext)) = terminated(tuple((chunk_size, chunk_ext)), crlf)(&line[..])
+            .map_err(|_| invalid_data(FromUtf8Err::init(line.as_slice(), ErrorKind::Chunk)))?;
+
+        std::str::from_utf8(size)
+            .ok()
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or_else(|| invalid_data(FromUtf8Err::init(size, ErrorKind::Chunk)))
+    }
+
+    fn read_trailers(&mut self, headers: &HeaderMap) -> io::Result<HeaderMap> {
+        let mut section = Vec::new();
+        while !section.ends_with(b"\r\n\r\n") && section != b"\r\n" {
+            self.read_line(
+                &mut section,
+                self.config.max_head_size,
+                ErrorKind::HeadTooLarge,
+            )?;
+        }
+
+        check_fields(&section, 0, 0, &self.config).map_err(invalid_data)?;
+        let (_, fields, _) = parse_field_lines(&section, &self.config).map_err(invalid_data)?;
+        let trailers = header_map(&section, &fields, &self.config).map_err(invalid_data)?;
+        validate_trailers(headers, &trailers).map_err(invalid_data)?;
+
+        Ok(trailers)
+    }
+
+    fn read_body(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match std::mem::replace(&mut self.body, BodyState::Done) {
+                BodyState::Done => return Ok(0),
+                BodyState::Fixed(remaining) => {
+                    let len = self.copy_to(out, remaining)?;
+                    if len == 0 && !out.is_empty() {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    if remaining > len {
+                        self.body = BodyState::Fixed(remaining - len);
+                    }
+
+                    return Ok(len);
+                }
+                BodyState::UntilClose => {
+                    let len = self.copy_to(out, usize::MAX)?;
+                    if len != 0 || out.is_empty() {
+                        self.body = BodyState::UntilClose;
+                    }
+
+                    return Ok(len);
+                }
+                BodyState::Chunked(Chunk::Size, headers) => match self.read_chunk_size()? {
+                    0 => {
+                        self.trailers = Some(self.read_trailers(&headers)?);
+                        return Ok(0);
+                    }
+                    size => self.body = BodyState::Chunked(Chunk::Data(size), headers),
+                },
+                BodyState::Chunked(Chunk::Data(remaining), headers) => {
+                    let len = self.copy_to(out, remaining)?;
+                    if len == 0 && !out.is_empty() {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+
+                    let next = match remaining - len {
+                        0 => Chunk::DataEnd,
+                        remaining => Chunk::Data(remaining),
+                    };
+                    self.body = BodyState::Chunked(next, headers);
+
+                    return Ok(len);
+                }
+                BodyState::Chunked(Chunk::DataEnd, headers) => {
+                    let mut line = Vec::new();
+                    self.read_line(&mut line, 2, ErrorKind::Chunk)?;
+                    if line != b"\r\n" {
+                        let err = FromUtf8Err::init(line, ErrorKind::Chunk);
+                        return Err(invalid_data(err));
+                    }
+
+                    self.body = BodyState::Chunked(Chunk::Size, headers);
+                }
+            }
+        }
+    }
+}
+
+pub struct BodyReader<'a, R> {
+    reader: &'a mut MessageReader<R>,
+}
+
+impl<R: BufRead> BodyReader<'_, R> {
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.reader.trailers()
+    }
+}
+
+impl<R: BufRead> Read for BodyReader<'_, R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        self.reader.read_body(out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn test_read_requests() {
+        let input = b"POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nHelloPUT /b HTTP/1.1\r\ntrailer: expires\r\ntransfer-encoding: chunked\r\n\r\n5;x=1\r\nHello\r\n6\r\n World\r\n0\r\nexpires: never\r\n\r\nGET /c HTTP/1.1\r\n\r\n";
+        let mut reader = MessageReader::new(BufReader::with_capacity(7, &input[..]));
+
+        let mut req = reader.read_request().unwrap().unwrap();
+        assert_eq!(req.uri(), "/a");
+        let mut body = Vec::new();
+        req.body_mut().read_to_end(&mut body).unwrap();
+        assert_eq!(body, b"Hello");
+
+        let mut req = reader.read_request().unwrap().unwrap();
+        assert_eq!(req.uri(), "/b");
+        let mut body = String::new();
+        req.body_mut().read_to_string(&mut body).unwrap();
+        assert_eq!(body, "Hello World");
+        assert_eq!(
+            req.body().trailers().unwrap().get("expires").unwrap(),
+            "never"
+        );
+
+        let req = reader.read_request().unwrap().unwrap();
+        assert_eq!(req.uri(), "/c");
+        assert!(reader.read_request().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_skip_unread_body() {
+        let input = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHelloHTTP/1.1 204 No Content\r\n\r\nHTTP/1.1 200 OK\r\n\r\nuntil close";
+        let mut reader = MessageReader::new(&input[..]);
+
+        let res = reader.read_response(&Method::GET).unwrap().unwrap();
+        assert_eq!(res.status(), 200);
+
+        let res = reader.read_response(&Method::GET).unwrap().unwrap();
+        assert_eq!(res.status(), 204);
+
+        let mut res = reader.read_response(&Method::GET).unwrap().unwrap();
+        let mut body = Vec::new();
+        res.body_mut().read_to_end(&mut body).unwrap();
+        assert_eq!(body, b"until close");
+        assert!(reader.read_response(&Method::GET).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let mut reader = MessageReader::new(&b"GET / HTTP/1.1\r\nHost"[..]);
+        let err = reader.read_request().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = MessageReader::new(&b"GET / HTTP/1.1\r\nBad Header\r\n\r\n"[..]);
+        let err = reader.read_request().err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = err.into_inner().unwrap().downcast::<FromUtf8Err>().unwrap();
+        assert_eq!(err.kind(), &ErrorKind::Header);
+
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+        let mut reader = MessageReader::new(&input[..]);
+        let mut req = reader.read_request().unwrap().unwrap();
+        let err = req.body_mut().read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let input = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabcd\r\n0\r\n\r\n";
+        let mut reader = MessageReader::new(&input[..]);
+        let mut req = reader.read_request().unwrap().unwrap();
+        let err = req.body_mut().read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,10 +1,40 @@
 use crate::error::*;
-use crate::FromUtf8;
+use crate::http_elements::{message_slices, request_line, IoSlices};
 use crate::PartialRequest;
-use http::Request;
+use crate::{FromUtf8, FromUtf8Framed, IntoUtf8, WriteUtf8};
+use http::header::{CONTENT_LENGTH, TRANSFER_ENCODING};
+use http::{HeaderValue, Request};
+
+impl IntoUtf8 for Request<Vec<u8>> {
+    fn into_utf8(&self) -> Result<Vec<u8>, ()> {
+        Ok(self.to_io_slices()?.to_vec())
+    }
+}
+
+impl<T: AsRef<[u8]>> WriteUtf8 for Request<T> {
+    fn to_io_slices(&self) -> Result<IoSlices<'_>, ()> {
+        let start_line = request_line(self.method(), self.uri(), self.version())?;
+        let body = self.body().as_ref();
+
+        let framed;
+        let headers = if body.is_empty()
+            || self.headers().contains_key(CONTENT_LENGTH)
+            || self.headers().contains_key(TRANSFER_ENCODING)
+        {
+            self.headers()
+        } else {
+            let mut headers = self.headers().clone();
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+            framed = headers;
+            &framed
+        };
+
+        Ok(message_slices(start_line, headers, self.extensions(), body))
+    }
+}
 
 impl<T> FromUtf8<T> for Request<T> {
-    fn from_utf8<'a>(buf: &'a [u8], body: T) -> Result<Self, FromUtf8Err>
+    fn from_utf8(buf: &[u8], body: T) -> Result<Self, FromUtf8Err>
     where
         Self: Sized,
     {
@@ -17,10 +47,26 @@ impl<T> FromUtf8<T> for Request<T> {
     }
 }
 
+impl FromUtf8Framed for Request<Vec<u8>> {
+    fn from_utf8_framed(buf: &[u8]) -> Result<(Self, &[u8]), FromUtf8Err>
+    where
+        Self: Sized,
+    {
+        PartialRequest::builder(buf)
+            .method()?
+            .uri()?
+            .version()?
+            .headers()?
+            .framed()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use http::{Method, Version};
 
+    use crate::{ObsFoldPolicy, ParserConfig, RawHead};
+
     use super::*;
 
     #[test]
@@ -40,4 +86,133 @@ mod test {
         assert_eq!(req.headers().get("Accept-Language").unwrap(), "");
         assert_eq!(req.body(), &b"ThisIsBody");
     }
+
+    #[test]
+    fn test_into_utf8() {
+        let input = b"POST /submit?a=1 HTTP/1.1\r\nhost: example.com\r\ncontent-length: 10\r\n\r\nThisIsBody";
+
+        let (req, _) = Request::from_utf8_framed(input).unwrap();
+        assert_eq!(req.into_utf8().unwrap(), input);
+
+        let req = Request::builder()
+            .method("PUT")
+            .uri("http://example.com/upload")
+            .header("Transfer-Encoding", "chunked")
+            .body(b"Hello".to_vec())
+            .unwrap();
+        let output = req.into_utf8().unwrap();
+        assert_eq!(
+            output,
+            b"PUT http://example.com/upload HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n"
+        );
+
+        let (parsed, rest) = Request::from_utf8_framed(&output).unwrap();
+        assert_eq!(parsed.method(), req.method());
+        assert_eq!(parsed.uri(), req.uri());
+        assert_eq!(parsed.body(), req.body());
+        assert!(rest.is_empty());
+
+        let req = Request::post("/").body(b"abc".to_vec()).unwrap();
+        let output = req.into_utf8().unwrap();
+        assert_eq!(output, b"POST / HTTP/1.1\r\ncontent-length: 3\r\n\r\nabc");
+
+        let (parsed, rest) = Request::from_utf8_framed(&output).unwrap();
+        assert_eq!(parsed.body(), b"abc");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = b"GET /hello.htm HTTP/1.1\r\nHost: example.com\r\nBad Header\r\n\r\n";
+
+        let err = Request::from_utf8(input, ()).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::Header);
+        assert_eq!((err.offset(), err.line(), err.column()), (47, 3, 4));
+        assert_eq!(err.input(), b" Header");
+
+        let mut partial = PartialRequest::new();
+        assert!(partial.feed(&input[..30]).unwrap_err().is_incomplete());
+        let err = partial.feed(&input[30..]).unwrap_err();
+        assert_eq!((err.offset(), err.line(), err.column()), (47, 3, 4));
+        assert!(err.to_string().ends_with("\n3 | Bad Header\n  |    ^"));
+    }
+
+    #[test]
+    fn test_from_utf8_framed() {
+        let input = b"POST /submit HTTP/1.1\r\nHost: example.com\r\nContent-Length: 10\r\n\r\nThisIsBodyGET / HTTP/1.1\r\n\r\n";
+
+        let (req, rest) = Request::from_utf8_framed(input).unwrap();
+        assert_eq!(req.method(), Method::POST);
+        assert_eq!(req.body(), b"ThisIsBody");
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n\r\n");
+
+        let (req, rest) = Request::from_utf8_framed(rest).unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert!(req.body().is_empty());
+        assert!(rest.is_empty());
+
+        let err = Request::from_utf8_framed(&input[..60]).unwrap_err();
+        assert!(err.is_incomplete());
+    }
+
+    #[test]
+    fn test_raw_round_trip() {
+        let input = b"POST /submit HTTP/1.1\r\nHOST:example.com  \r\nx-Custom-Header:\ta\r\nTransfer-Encoding: chunked\r\n\r\n3;ext=1\r\nabc\r\n0\r\n\r\n";
+        let config = ParserConfig {
+            preserve_raw: true,
+            ..ParserConfig::default()
+        };
+
+        let (mut req, rest) = PartialRequest::builder_with_config(input, config)
+            .method()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .version()
+            .unwrap()
+            .headers()
+            .unwrap()
+            .framed()
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(req.body(), b"abc");
+        assert_eq!(req.into_utf8().unwrap(), input);
+
+        let (canonical, _) = Request::from_utf8_framed(input).unwrap();
+        assert!(canonical.extensions().get::<RawHead>().is_none());
+
+        req.headers_mut()
+            .insert("x-custom-header", "b".parse().unwrap());
+        *req.body_mut() = b"abcd".to_vec();
+        assert_eq!(
+            req.into_utf8().unwrap(),
+            b"POST /submit HTTP/1.1\r\nhost: example.com\r\nx-custom-header: b\r\ntransfer-encoding: chunked\r\n\r\n4\r\nabcd\r\n0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_round_trip_lenient() {
+        let input = b"GET / HTTP/1.1\r\nHost : a\r\nx: a\r\n b\r\n\r\n";
+        let config = ParserConfig {
+            preserve_raw: true,
+            lenient: true,
+            obs_fold: ObsFoldPolicy::Replace,
+            ..ParserConfig::default()
+        };
+
+        let (req, _) = PartialRequest::builder_with_config(input, config)
+            .method()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .version()
+            .unwrap()
+            .headers()
+            .unwrap()
+            .framed()
+            .unwrap();
+        assert_eq!(req.headers().get("host").unwrap(), "a");
+        assert_eq!(req.headers().get("x").unwrap(), "a b");
+        assert_eq!(req.into_utf8().unwrap(), input);
+    }
 }
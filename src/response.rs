@@ -1,29 +1,34 @@
 use http::Response;
 
-use crate::{partial_response::PartialResponse, FromUtf8, IntoUtf8};
+use crate::extensions::ReasonPhrase;
+use crate::http_elements::{message_slices, status_line, IoSlices};
+use crate::{partial_response::PartialResponse, FromUtf8, FromUtf8Framed, IntoUtf8, WriteUtf8};
 
 impl IntoUtf8 for Response<Vec<u8>> {
     fn into_utf8(&self) -> Result<Vec<u8>, ()> {
-        let mut result = Vec::new();
-        result.append(&mut self.version().into_utf8()?);
-        result.push(b' ');
-
-        result.append(&mut self.status().into_utf8()?);
-        result.push(b'\r');
-        result.push(b'\n');
-
-        result.append(&mut self.headers().into_utf8()?);
-        result.push(b'\r');
-        result.push(b'\n');
+        Ok(self.to_io_slices()?.to_vec())
+    }
+}
 
-        result.append(&mut self.body().clone());
+impl<T: AsRef<[u8]>> WriteUtf8 for Response<T> {
+    fn to_io_slices(&self) -> Result<IoSlices<'_>, ()> {
+        let start_line = status_line(
+            self.version(),
+            self.status(),
+            self.extensions().get::<ReasonPhrase>(),
+        )?;
 
-        Ok(result)
+        Ok(message_slices(
+            start_line,
+            self.headers(),
+            self.extensions(),
+            self.body().as_ref(),
+        ))
     }
 }
 
 impl<T> FromUtf8<T> for Response<T> {
-    fn from_utf8<'a>(buf: &'a [u8], body: T) -> Result<Self, crate::FromUtf8Err>
+    fn from_utf8(buf: &[u8], body: T) -> Result<Self, crate::FromUtf8Err>
     where
         Self: Sized,
     {
@@ -34,3 +39,163 @@ impl<T> FromUtf8<T> for Response<T> {
             .body(body))
     }
 }
+
+impl FromUtf8Framed for Response<Vec<u8>> {
+    fn from_utf8_framed(buf: &[u8]) -> Result<(Self, &[u8]), crate::FromUtf8Err>
+    where
+        Self: Sized,
+    {
+        PartialResponse::builder(buf)
+            .version()?
+            .status()?
+            .headers()?
+            .framed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::StatusCode;
+
+    use crate::Trailers;
+
+    use super::*;
+
+    #[test]
+    fn test_from_utf8_framed() {
+        let input =
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHelloHTTP/1.1 404 Not Found\r\n\r\nGone";
+
+        let (res, rest) = Response::from_utf8_framed(input).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body(), b"Hello");
+
+        let (res, rest) = Response::from_utf8_framed(rest).unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(res.body(), b"Gone");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_reason_phrase() {
+        let input = b"HTTP/1.1 200 Everything Is Fine\r\ncontent-length: 0\r\n\r\n";
+
+        let (res, _) = Response::from_utf8_framed(input).unwrap();
+        assert_eq!(
+            res.extensions().get::<ReasonPhrase>().unwrap().as_bytes(),
+            b"Everything Is Fine"
+        );
+        assert_eq!(res.into_utf8().unwrap(), input);
+
+        let (mut res, _) = Response::from_utf8_framed(b"HTTP/1.1 200 OK\r\n\r\n").unwrap();
+        *res.status_mut() = StatusCode::NOT_FOUND;
+        assert_eq!(res.into_utf8().unwrap(), b"HTTP/1.1 404 Not Found\r\n\r\n");
+
+        let res = Response::builder().status(404).body(Vec::new()).unwrap();
+        assert_eq!(res.into_utf8().unwrap(), b"HTTP/1.1 404 Not Found\r\n\r\n");
+
+        let res = Response::builder().status(299).body(Vec::new()).unwrap();
+        assert_eq!(res.into_utf8().unwrap(), b"HTTP/1.1 299 \r\n\r\n");
+    }
+
+    #[test]
+    fn test_status_line_error() {
+        let err = Response::from_utf8(b"HTTP/1.1 2x0 OK\r\n\r\n", ()).unwrap_err();
+        assert_eq!(err.kind(), &crate::ErrorKind::StatusCode);
+        assert_eq!((err.offset(), err.line(), err.column()), (9, 1, 10));
+    }
+
+    #[test]
+    fn test_chunked_round_trip() {
+        let input = b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n6\r\n World\r\n0\r\n\r\n";
+
+        let (res, rest) = Response::from_utf8_framed(input).unwrap();
+        assert_eq!(res.body(), b"Hello World");
+        assert!(rest.is_empty());
+
+        assert_eq!(
+            res.into_utf8().unwrap(),
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\nB\r\nHello World\r\n0\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_trailers() {
+        let input = b"HTTP/1.1 200 OK\r\ntrailer: Expires\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\nexpires: never\r\n\r\n";
+
+        let (res, rest) = Response::from_utf8_framed(input).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(res.body(), b"Hello");
+
+        let trailers = res.extensions().get::<Trailers>().unwrap();
+        assert_eq!(trailers.headers().get("expires").unwrap(), "never");
+        assert!(res.headers().get("expires").is_none());
+        assert_eq!(res.into_utf8().unwrap(), input);
+
+        let undeclared =
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n0\r\nexpires: never\r\n\r\n";
+        let err = Response::from_utf8_framed(undeclared).unwrap_err();
+        assert_eq!(err.kind(), &crate::ErrorKind::Trailer);
+        assert_eq!((err.offset(), err.line(), err.column()), (50, 5, 1));
+
+        let mut flood =
+            b"HTTP/1.1 200 OK\r\ntrailer: x\r\ntransfer-encoding: chunked\r\n\r\n0\r\n".to_vec();
+        flood.extend_from_slice(&b"x: 1\r\n".repeat(5000));
+        flood.extend_from_slice(b"\r\n");
+        let config = crate::ParserConfig {
+            max_header_count: 10,
+            ..crate::ParserConfig::default()
+        };
+        let err = PartialResponse::builder_with_config(&flood, config)
+            .version()
+            .unwrap()
+            .status()
+            .unwrap()
+            .headers()
+            .unwrap()
+            .framed()
+            .unwrap_err();
+        assert_eq!(err.kind(), &crate::ErrorKind::TooManyHeaders);
+        assert_eq!((err.offset(), err.line(), err.column()), (122, 16, 1));
+    }
+
+    #[test]
+    fn test_io_slices() {
+        let body = vec![b'x'; 1 << 16];
+        let res = Response::builder()
+            .header("content-length", body.len())
+            .body(&body[..])
+            .unwrap();
+
+        let slices = res.to_io_slices().unwrap();
+        let io_slices = slices.as_io_slices();
+        assert_eq!(io_slices.len(), 2);
+        assert_eq!(
+            &*io_slices[0],
+            b"HTTP/1.1 200 OK\r\ncontent-length: 65536\r\n\r\n"
+        );
+        assert_eq!(io_slices[1].as_ptr(), body.as_ptr());
+        assert_eq!(slices.len(), 42 + body.len());
+
+        let input = b"HTTP/1.1 200 OK\r\ntrailer: Expires\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\nexpires: never\r\n\r\n";
+        let (mut res, _) = Response::from_utf8_framed(input).unwrap();
+
+        let mut output = Vec::new();
+        res.write_to(&mut output).unwrap();
+        assert_eq!(output, input);
+
+        res.extensions_mut().clear();
+        res.headers_mut().remove("trailer");
+        let slices = res.to_io_slices().unwrap();
+        assert_eq!(slices.as_io_slices()[1].as_ptr(), res.body().as_ptr());
+        assert_eq!(slices.to_vec(), res.into_utf8().unwrap());
+        assert_eq!(
+            slices.to_vec(),
+            b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n5\r\nHello\r\n0\r\n\r\n"
+        );
+
+        let mut full = [0u8; 8];
+        let err = res.write_to(&mut &mut full[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::basic_combinator::tchar;
+
+const KNOWN_CODINGS: [&[u8]; 6] = [
+    b"chunked",
+    b"compress",
+    b"deflate",
+    b"gzip",
+    b"x-compress",
+    b"x-gzip",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    ContentLengthWithTransferEncoding,
+    ConflictingContentLength,
+    ObfuscatedTransferEncoding,
+    WhitespaceBeforeColon,
+    ObsFold,
+    BareLf,
+    MissingHost,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    kind: FindingKind,
+    offset: usize,
+    line: usize,
+}
+
+impl Finding {
+    fn new(kind: FindingKind, offset: usize, line: usize) -> Self {
+        Self { kind, offset, line }
+    }
+
+    pub fn kind(&self) -> FindingKind {
+        self.kind
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+struct Field<'a> {
+    name: &'a [u8],
+    value: Vec<u8>,
+    folded: bool,
+    offset: usize,
+    line: usize,
+}
+
+fn trim_ows(mut input: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = input {
+        input = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = input {
+        input = rest;
+    }
+    input
+}
+
+fn is_token(input: &[u8]) -> bool {
+    !input.is_empty() && input.chunks(1).all(|c| tchar(c).is_ok())
+}
+
+pub fn validate_request(input: &[u8]) -> Vec<Finding> {
+    validate(input, true)
+}
+
+pub fn validate_response(input: &[u8]) -> Vec<Finding> {
+    validate(input, false)
+}
+
+fn validate(input: &[u8], request: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut start_line = None;
+    let mut fields: Vec<Field> = Vec::new();
+
+    let mut offset = 0;
+    let mut line = 1;
+    while let Some(len) = input[offset..].iter().position(|&c| c == b'\n') {
+        let end = offset + len;
+        let content = match input[offset..end].strip_suffix(b"\r") {
+            Some(content) => content,
+            None => {
+                findings.push(Finding::new(FindingKind::BareLf, end, line));
+                &input[offset..end]
+            }
+        };
+
+        if start_line.is_none() {
+            start_line = Some(content);
+        } else if content.is_empty() {
+            break;
+        } else if let [b' ' | b'\t', ..] = content {
+            findings.push(Finding::new(FindingKind::ObsFold, offset, line));
+
+            if let Some(field) = fields.last_mut() {
+                field.value.push(b' ');
+                field.value.extend_from_slice(trim_ows(content));
+                field.folded = true;
+            }
+        } else if let Some(colon) = content.iter().position(|&c| c == b':') {
+            let name = &content[..colon];
+            if name.ends_with(b" ") || name.ends_with(b"\t") {
+                findings.push(Finding::new(
+                    FindingKind::WhitespaceBeforeColon,
+                    offset,
+                    line,
+                ));
+            }
+
+            fields.push(Field {
+                name: trim_ows(name),
+                value: trim_ows(&content[colon + 1..]).to_vec(),
+                folded: false,
+                offset,
+                line,
+            });
+        }
+
+        offset = end + 1;
+        line += 1;
+    }
+
+    let named = |name: &'static str| {
+        fields
+            .iter()
+            .filter(move |field| field.name.eq_ignore_ascii_case(name.as_bytes()))
+    };
+
+    let mut lengths: Vec<&[u8]> = Vec::new();
+    for field in named("content-length") {
+        for value in field.value.split(|&c| c == b',').map(trim_ows) {
+            if !lengths.contains(&value) {
+                lengths.push(value);
+            }
+        }
+        if lengths.len() > 1 {
+            findings.push(Finding::new(
+                FindingKind::ConflictingContentLength,
+                field.offset,
+                field.line,
+            ));
+            break;
+        }
+    }
+
+    let mut codings = Vec::new();
+    let mut obfuscated = false;
+    for field in named("transfer-encoding") {
+        let names: Vec<_> = field
+            .value
+            .split(|&c| c == b',')
+            .map(|coding| trim_ows(coding.split(|&c| c == b';').next().unwrap_or_default()))
+            .collect();
+
+        if field.folded
+            || names.iter().any(|name| {
+                !is_token(name)
+                    || !KNOWN_CODINGS
+                        .iter()
+                        .any(|known| name.eq_ignore_ascii_case(known))
+            })
+        {
+            obfuscated = true;
+            findings.push(Finding::new(
+                FindingKind::ObfuscatedTransferEncoding,
+                field.offset,
+                field.line,
+            ));
+        }
+
+        codings.extend(names.into_iter().map(<[u8]>::to_ascii_lowercase));
+    }
+
+    let chunked = codings
+        .iter()
+        .filter(|&coding| coding == b"chunked")
+        .count();
+    let misplaced =
+        chunked > 1 || (chunked == 1 && codings.last().is_some_and(|last| last != b"chunked"));
+    if misplaced && !obfuscated {
+        if let Some(field) = named("transfer-encoding").next_back() {
+            findings.push(Finding::new(
+                FindingKind::ObfuscatedTransferEncoding,
+                field.offset,
+                field.line,
+            ));
+        }
+    }
+
+    if let (Some(cl), Some(te)) = (
+        named("content-length").next(),
+        named("transfer-encoding").next(),
+    ) {
+        let later = if cl.offset > te.offset { cl } else { te };
+        findings.push(Finding::new(
+            FindingKind::ContentLengthWithTransferEncoding,
+            later.offset,
+            later.line,
+        ));
+    }
+
+    let version = start_line.and_then(|start_line| {
+        start_line
+            .split(|&c| c == b' ' || c == b'\t')
+            .filter(|token| !token.is_empty())
+            .nth(2)
+    });
+    let http_11 = version == Some(b"HTTP/1.1");
+    if request && http_11 && named("host").next().is_none() {
+        findings.push(Finding::new(FindingKind::MissingHost, 0, 1));
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(findings: &[Finding]) -> Vec<FindingKind> {
+        findings.iter().map(Finding::kind).collect()
+    }
+
+    #[test]
+    fn test_clean_request() {
+        let input = b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5, 5\r\n\r\nhello";
+        assert!(validate_request(input).is_empty());
+
+        let input = b"GET / HTTP/1.0\r\n\r\n";
+        assert!(validate_request(input).is_empty());
+
+        let input = b"GET / HTTP/1.1 \r\n\r\n";
+        assert_eq!(kinds(&validate_request(input)), [FindingKind::MissingHost]);
+    }
+
+    #[test]
+    fn test_smuggling_findings() {
+        let input = b"POST / HTTP/1.1\r\nContent-Length: 5\nTransfer-Encoding : chunked\r\nContent-Length: 6\r\n\r\n0\r\n\r\n";
+
+        let findings = validate_request(input);
+        assert_eq!(
+            kinds(&findings),
+            [
+                FindingKind::BareLf,
+                FindingKind::WhitespaceBeforeColon,
+                FindingKind::ConflictingContentLength,
+                FindingKind::ContentLengthWithTransferEncoding,
+                FindingKind::MissingHost,
+            ]
+        );
+        assert_eq!((findings[0].offset(), findings[0].line()), (34, 2));
+        assert_eq!((findings[1].offset(), findings[1].line()), (35, 3));
+        assert_eq!(findings[2].line(), 4);
+        assert_eq!(findings[3].line(), 3);
+    }
+
+    #[test]
+    fn test_obfuscated_transfer_encoding() {
+        for value in [
+            &b"\x0bchunked"[..],
+            b"xchunked",
+            b"chunked, identity",
+            b"\"chunked\"",
+            b"chunked, chunked",
+        ] {
+            let mut input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: ".to_vec();
+            input.extend_from_slice(value);
+            input.extend_from_slice(b"\r\n\r\n");
+
+            assert_eq!(
+                kinds(&validate_response(&input)),
+                [FindingKind::ObfuscatedTransferEncoding],
+                "{:?}",
+                String::from_utf8_lossy(value)
+            );
+        }
+
+        let input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, CHUNKED\r\n\r\n";
+        assert!(validate_response(input).is_empty());
+
+        let input = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip\r\n ,chunked\r\n\r\n";
+        let findings = validate_response(input);
+        assert_eq!(
+            kinds(&findings),
+            [
+                FindingKind::ObsFold,
+                FindingKind::ObfuscatedTransferEncoding
+            ]
+        );
+        assert_eq!((findings[0].offset(), findings[0].line()), (42, 3));
+        assert_eq!(findings[1].line(), 2);
+    }
+}